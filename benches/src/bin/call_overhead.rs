@@ -0,0 +1,62 @@
+//! Measures the per-call overhead of calling a packed function from Rust.
+//!
+//! Run with `cargo run --release --bin call_overhead` from the `benches` directory.
+
+#[macro_use]
+extern crate tvm_frontend as tvm;
+
+use std::time::Instant;
+
+use tvm::*;
+
+const WARMUP: usize = 1_000;
+const CALLS: usize = 100_000;
+
+fn measure<F: FnMut()>(name: &str, mut f: F) {
+    for _ in 0..WARMUP {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..CALLS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    println!(
+        "{:<32} {:>10.1} ns/call",
+        name,
+        nanos as f64 / CALLS as f64
+    );
+}
+
+fn main() {
+    register_global_func! {
+        fn bench_sum(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+            let mut ret = 0i64;
+            for arg in args.iter() {
+                ret += arg.to_int();
+            }
+            Ok(TVMRetValue::from(ret))
+        }
+    }
+    let func = Function::get_function("bench_sum", true).expect("`bench_sum` is registered");
+    let (a, b, c) = (1i64, 2i64, 3i64);
+
+    measure("call_packed!", || {
+        let func = func.clone();
+        let ret = call_packed!(func, &a, &b, &c).unwrap();
+        assert_eq!(ret.to_int(), 6);
+    });
+
+    let mut builder = function::Builder::with_capacity(func.clone(), 3);
+    measure("reused function::Builder", || {
+        let ret = builder.arg(&a).arg(&b).arg(&c).invoke().unwrap();
+        assert_eq!(ret.to_int(), 6);
+    });
+
+    let args = [ArgValue::from(a), ArgValue::from(b), ArgValue::from(c)];
+    measure("Function::call", || {
+        let ret = func.call(&args).unwrap();
+        assert_eq!(ret.to_int(), 6);
+    });
+}
@@ -5,6 +5,7 @@ extern crate tvm_frontend as tvm;
 
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     error::Error,
     fs::{self, File},
     path::Path,
@@ -61,7 +62,7 @@ fn main() -> Result<(), Box<Error>> {
         &ctx.device_id
     )?;
     // get graph runtime module
-    let graph_runtime_module = runtime_create_fn_ret.to_module();
+    let graph_runtime_module = Module::try_from(runtime_create_fn_ret)?;
     // get the registered `load_params` from runtime module
     let load_param_fn = graph_runtime_module
         .get_function("load_params", false)
@@ -1,9 +1,15 @@
 //! Provides [`TVMByteArray`] used for passing the model parameters
 //! (stored as byte-array) to a runtime module.
 //!
+//! A [`TVMByteArray`] either borrows its bytes from a Rust buffer, in which case it
+//! cannot outlive that buffer, or owns them as it happens for byte-arrays returned from TVM.
+//!
 //! For more detail, please see the example `resnet` in `examples` repository.
 
-use std::os::raw::c_char;
+use std::{borrow::Cow, os::raw::c_char, slice};
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
 
 use ts;
 
@@ -15,42 +21,121 @@ use ts;
 /// let v = b"hello".to_vec();
 /// let barr = TVMByteArray::from(&v);
 /// assert_eq!(barr.len(), v.len());
-/// assert_eq!(barr.data(), vec![104i8, 101, 108, 108, 111]);
+/// assert_eq!(barr.as_slice(), b"hello");
 /// ```
-#[derive(Debug, Clone)]
-pub struct TVMByteArray {
+#[derive(Debug)]
+pub struct TVMByteArray<'a> {
+    // points into `buf` which is either borrowed or heap allocated,
+    // so it stays valid even when the struct is moved.
     pub(crate) inner: ts::TVMByteArray,
+    buf: Cow<'a, [u8]>,
 }
 
-impl TVMByteArray {
-    pub(crate) fn new(barr: ts::TVMByteArray) -> TVMByteArray {
-        TVMByteArray { inner: barr }
+impl<'a> TVMByteArray<'a> {
+    fn new(buf: Cow<'a, [u8]>) -> Self {
+        let inner = ts::TVMByteArray {
+            data: buf.as_ptr() as *const c_char,
+            size: buf.len(),
+        };
+        TVMByteArray { inner, buf }
+    }
+
+    /// Copies a byte-array owned by TVM, such as a return value, into an owned byte-array.
+    pub(crate) unsafe fn from_raw(barr: &ts::TVMByteArray) -> TVMByteArray<'static> {
+        let data = if barr.size == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(barr.data as *const u8, barr.size).to_vec()
+        };
+        TVMByteArray::from(data)
     }
 
     /// Gets the length of the underlying byte-array
     pub fn len(&self) -> usize {
-        self.inner.size
+        self.buf.len()
     }
 
-    /// Gets the underlying byte-array as `Vec<i8>`
-    pub fn data(&self) -> Vec<i8> {
-        unsafe {
-            let sz = self.len();
-            let mut ret_buf = Vec::with_capacity(sz);
-            ret_buf.set_len(sz);
-            self.inner.data.copy_to(ret_buf.as_mut_ptr(), sz);
-            ret_buf
+    /// Returns `true` if the underlying byte-array is empty
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Gets the underlying byte-array as a slice
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Copies the underlying byte-array to a `Vec<u8>`
+    pub fn data(&self) -> Vec<u8> {
+        self.buf.to_vec()
+    }
+
+    /// Returns `true` if the byte-array owns its bytes
+    pub fn is_owned(&self) -> bool {
+        match self.buf {
+            Cow::Owned(_) => true,
+            Cow::Borrowed(_) => false,
         }
     }
+
+    /// Converts to a byte-array owning its bytes, copying them if borrowed
+    pub fn into_owned(self) -> TVMByteArray<'static> {
+        TVMByteArray::from(self.buf.into_owned())
+    }
+
+    /// Converts to the underlying bytes, copying them if borrowed
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buf.into_owned()
+    }
 }
 
-impl<'a> From<&'a Vec<u8>> for TVMByteArray {
-    fn from(arg: &Vec<u8>) -> Self {
-        let barr = ts::TVMByteArray {
-            data: arg.as_ptr() as *const c_char,
-            size: arg.len(),
-        };
-        TVMByteArray::new(barr)
+impl<'a> Clone for TVMByteArray<'a> {
+    fn clone(&self) -> Self {
+        TVMByteArray::new(self.buf.clone())
+    }
+}
+
+impl<'a> AsRef<[u8]> for TVMByteArray<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<'a> From<&'a [u8]> for TVMByteArray<'a> {
+    fn from(arg: &'a [u8]) -> Self {
+        TVMByteArray::new(Cow::Borrowed(arg))
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for TVMByteArray<'a> {
+    fn from(arg: &'a Vec<u8>) -> Self {
+        TVMByteArray::new(Cow::Borrowed(arg.as_slice()))
+    }
+}
+
+impl<'a> From<Vec<u8>> for TVMByteArray<'a> {
+    fn from(arg: Vec<u8>) -> Self {
+        TVMByteArray::new(Cow::Owned(arg))
+    }
+}
+
+impl<'a> From<TVMByteArray<'a>> for Vec<u8> {
+    fn from(barr: TVMByteArray<'a>) -> Self {
+        barr.into_vec()
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<'a> From<TVMByteArray<'a>> for Bytes {
+    fn from(barr: TVMByteArray<'a>) -> Self {
+        Bytes::from(barr.into_vec())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<Bytes> for TVMByteArray<'static> {
+    fn from(bytes: Bytes) -> Self {
+        TVMByteArray::from(bytes.to_vec())
     }
 }
 
@@ -63,10 +148,27 @@ mod tests {
         let v = vec![1u8, 2, 3];
         let barr = TVMByteArray::from(&v);
         assert_eq!(barr.len(), v.len());
-        assert_eq!(barr.data(), vec![1i8, 2, 3]);
+        assert_eq!(barr.data(), vec![1u8, 2, 3]);
+        assert!(!barr.is_owned());
         let v = b"hello".to_vec();
         let barr = TVMByteArray::from(&v);
         assert_eq!(barr.len(), v.len());
-        assert_eq!(barr.data(), vec![104i8, 101, 108, 108, 111]);
+        assert_eq!(barr.data(), vec![104u8, 101, 108, 108, 111]);
+        assert_eq!(barr.as_slice(), &v[..]);
+    }
+
+    #[test]
+    fn owned() {
+        let barr = TVMByteArray::from(b"hello".to_vec());
+        assert!(barr.is_owned());
+        let cloned = barr.clone();
+        drop(barr);
+        assert_eq!(cloned.inner.size, 5);
+        assert_eq!(cloned.inner.data as *const u8, cloned.as_slice().as_ptr());
+        let copied = unsafe { TVMByteArray::from_raw(&cloned.inner) };
+        assert_eq!(Vec::from(copied), b"hello".to_vec());
+        let borrowed = TVMByteArray::from(&b"world"[..]).into_owned();
+        assert!(borrowed.is_owned());
+        assert!(TVMByteArray::from(Vec::new()).is_empty());
     }
 }
@@ -0,0 +1,458 @@
+//! Provides the [`Array`] and [`Map`] wrappers around TVM container nodes, which many
+//! compiler-side global functions take or return.
+//!
+//! Containers are created and read through the `_Array`, `_ArrayGetItem`, `_ArraySize`,
+//! `_Map`, `_MapSize`, `_MapGetItem` and `_MapItems` global functions. Their elements are
+//! nodes, i.e. [`NodeRef`] or nested containers, or strings. Strings are stored as
+//! `StringImm` nodes created by `_str`, except for the keys of a string map.
+//!
+//! Creating a container calls into TVM, which can fail, so containers are built with
+//! `TryFrom` or `try_from_iter` instead of `FromIterator`. For the same reason there is no
+//! `Index` implementation: it must return a reference and cannot fail, while an element is
+//! read from TVM on each access. Use `get` instead.
+//!
+//! # Example
+//!
+//! ```
+//! let shapes: Map<String, Array<NodeRef>> = Map::try_from(vec![("data".to_owned(), shape)]).unwrap();
+//! let shape = shapes.get(&"data".to_owned()).unwrap();
+//! for dim in shape.iter() {
+//!     println!("{}", dim.unwrap().type_key().unwrap());
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::{self, Debug, Formatter},
+    hash::Hash,
+    marker::PhantomData,
+    mem,
+};
+
+use internal_api;
+use ArgValue;
+use Error;
+use ErrorKind;
+use NodeRef;
+use Result;
+use RetValue;
+use TVMArgValue;
+
+/// Types which can be read from an [`Array`] or a [`Map`].
+pub trait FromContainer: Sized {
+    /// Converts a value returned from the container functions into an element.
+    fn from_ret_value(val: RetValue) -> Result<Self>;
+}
+
+/// Types which can be stored in an [`Array`] or a [`Map`].
+pub trait ContainerElem: FromContainer {
+    /// Converts the element into an argument of the container functions.
+    fn to_arg_value(&self) -> ArgValue;
+
+    /// Converts the element into a node, which an `Array` element or a `Map` value must be.
+    fn to_node_arg(&self) -> Result<ArgValue> {
+        Ok(self.to_arg_value())
+    }
+}
+
+impl FromContainer for NodeRef {
+    fn from_ret_value(val: RetValue) -> Result<Self> {
+        NodeRef::try_from(val)
+    }
+}
+
+impl ContainerElem for NodeRef {
+    fn to_arg_value(&self) -> ArgValue {
+        ArgValue::Node(self.clone())
+    }
+}
+
+impl FromContainer for String {
+    // strings, including the keys from `_MapItems`, are returned as `StringImm` nodes
+    fn from_ret_value(val: RetValue) -> Result<Self> {
+        match val {
+            RetValue::Node(node) => String::try_from(node.get_attr("value")?),
+            other => String::try_from(other),
+        }
+    }
+}
+
+impl ContainerElem for String {
+    fn to_arg_value(&self) -> ArgValue {
+        ArgValue::Str(self.clone())
+    }
+
+    fn to_node_arg(&self) -> Result<ArgValue> {
+        call_api("_str", &[self.to_arg_value()])
+    }
+}
+
+/// Calls the container function `name`.
+fn call_api(name: &str, args: &[ArgValue]) -> Result<RetValue> {
+    internal_api::get_api(name.to_owned()).call(args)
+}
+
+/// Returns the size of `node` as given by the container function `name`.
+fn size(name: &str, node: &NodeRef) -> Result<usize> {
+    let size = i64::try_from(call_api(name, &[ArgValue::Node(node.clone())])?)?;
+    Ok(size as usize)
+}
+
+/// Wrapper around a TVM `Array` node with elements of type `T`.
+pub struct Array<T> {
+    node: NodeRef,
+    _elem: PhantomData<T>,
+}
+
+impl<T: FromContainer> Array<T> {
+    /// Wraps an `Array` node. The element type is not checked until the elements are read.
+    pub fn from_node(node: NodeRef) -> Self {
+        Array {
+            node,
+            _elem: PhantomData,
+        }
+    }
+
+    /// Returns the underlying node.
+    pub fn node(&self) -> &NodeRef {
+        &self.node
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> Result<usize> {
+        size("_ArraySize", &self.node)
+    }
+
+    /// Returns true if the array has no elements.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Gets the element at `index`.
+    pub fn get(&self, index: usize) -> Result<T> {
+        let item = call_api(
+            "_ArrayGetItem",
+            &[ArgValue::Node(self.node.clone()), ArgValue::from(index)],
+        )?;
+        T::from_ret_value(item)
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            array: self,
+            index: 0,
+            len: self.len(),
+        }
+    }
+
+    /// Copies the elements into a `Vec`.
+    pub fn to_vec(&self) -> Result<Vec<T>> {
+        self.iter().collect()
+    }
+}
+
+impl<T> Clone for Array<T> {
+    fn clone(&self) -> Self {
+        Array {
+            node: self.node.clone(),
+            _elem: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Array<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("Array").field(&self.node).finish()
+    }
+}
+
+impl<T: ContainerElem> Array<T> {
+    /// Creates an array from the elements of `iter`.
+    pub fn try_from_iter<I: IntoIterator<Item = T>>(iter: I) -> Result<Self> {
+        Array::try_from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: ContainerElem> TryFrom<Vec<T>> for Array<T> {
+    type Error = Error;
+
+    fn try_from(items: Vec<T>) -> Result<Self> {
+        let args = items
+            .iter()
+            .map(ContainerElem::to_node_arg)
+            .collect::<Result<Vec<_>>>()?;
+        let node = NodeRef::try_from(call_api("_Array", &args)?)?;
+        Ok(Array::from_node(node))
+    }
+}
+
+impl<T: FromContainer> FromContainer for Array<T> {
+    fn from_ret_value(val: RetValue) -> Result<Self> {
+        Ok(Array::from_node(NodeRef::try_from(val)?))
+    }
+}
+
+impl<T: FromContainer> ContainerElem for Array<T> {
+    fn to_arg_value(&self) -> ArgValue {
+        ArgValue::Node(self.node.clone())
+    }
+}
+
+impl<T> From<Array<T>> for ArgValue {
+    fn from(array: Array<T>) -> Self {
+        ArgValue::Node(array.node)
+    }
+}
+
+impl<T: FromContainer> TryFrom<ArgValue> for Array<T> {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<Self> {
+        Array::from_ret_value(val)
+    }
+}
+
+impl<'a, T> From<&'a Array<T>> for TVMArgValue<'a> {
+    fn from(array: &'a Array<T>) -> Self {
+        TVMArgValue::from(&array.node)
+    }
+}
+
+impl<'a, T: FromContainer> IntoIterator for &'a Array<T> {
+    type Item = Result<T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of an [`Array`].
+///
+/// Reading an element calls into TVM, so each item is a `Result`.
+pub struct Iter<'a, T: 'a> {
+    array: &'a Array<T>,
+    index: usize,
+    // the size is read once, and an error is reported as the only item
+    len: Result<usize>,
+}
+
+impl<'a, T: FromContainer> Iterator for Iter<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        let len = match self.len {
+            Ok(len) => len,
+            Err(_) => return mem::replace(&mut self.len, Ok(0)).err().map(Err),
+        };
+        if self.index >= len {
+            return None;
+        }
+        self.index += 1;
+        Some(self.array.get(self.index - 1))
+    }
+}
+
+/// Wrapper around a TVM `Map` node with keys of type `K` and values of type `V`.
+///
+/// Keys are either nodes or strings, the latter creating a string map. An empty map is
+/// always a node map.
+pub struct Map<K, V> {
+    node: NodeRef,
+    _elem: PhantomData<(K, V)>,
+}
+
+impl<K: ContainerElem, V: FromContainer> Map<K, V> {
+    /// Wraps a `Map` node. The key and value types are not checked until the items are read.
+    pub fn from_node(node: NodeRef) -> Self {
+        Map {
+            node,
+            _elem: PhantomData,
+        }
+    }
+
+    /// Returns the underlying node.
+    pub fn node(&self) -> &NodeRef {
+        &self.node
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> Result<usize> {
+        size("_MapSize", &self.node)
+    }
+
+    /// Returns true if the map has no items.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Gets the value of `key`, which is an error if the key is missing.
+    pub fn get(&self, key: &K) -> Result<V> {
+        let value = call_api(
+            "_MapGetItem",
+            &[ArgValue::Node(self.node.clone()), key.to_arg_value()],
+        )?;
+        V::from_ret_value(value)
+    }
+
+    /// Returns the items as `(key, value)` pairs.
+    pub fn items(&self) -> Result<Vec<(K, V)>> {
+        // `_MapItems` returns an array of alternating keys and values
+        let flat = Array::<RawElem>::from_ret_value(call_api(
+            "_MapItems",
+            &[ArgValue::Node(self.node.clone())],
+        )?)?
+        .to_vec()?;
+        if flat.len() % 2 != 0 {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "expected map items as key value pairs, but found {} elements",
+                flat.len()
+            )));
+        }
+        let mut items = Vec::with_capacity(flat.len() / 2);
+        let mut flat = flat.into_iter();
+        while let (Some(key), Some(value)) = (flat.next(), flat.next()) {
+            items.push((K::from_ret_value(key.0)?, V::from_ret_value(value.0)?));
+        }
+        Ok(items)
+    }
+}
+
+impl<K: ContainerElem + Eq + Hash, V: FromContainer> Map<K, V> {
+    /// Copies the items into a `HashMap`.
+    pub fn to_hash_map(&self) -> Result<HashMap<K, V>> {
+        Ok(self.items()?.into_iter().collect())
+    }
+}
+
+/// Value of a container item before it is converted to the element type.
+struct RawElem(RetValue);
+
+impl FromContainer for RawElem {
+    fn from_ret_value(val: RetValue) -> Result<Self> {
+        Ok(RawElem(val))
+    }
+}
+
+impl<K, V> Clone for Map<K, V> {
+    fn clone(&self) -> Self {
+        Map {
+            node: self.node.clone(),
+            _elem: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Debug for Map<K, V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("Map").field(&self.node).finish()
+    }
+}
+
+impl<K: ContainerElem, V: ContainerElem> Map<K, V> {
+    /// Creates a map from the `(key, value)` pairs of `iter`.
+    pub fn try_from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Result<Self> {
+        Map::try_from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<K: ContainerElem, V: ContainerElem> TryFrom<Vec<(K, V)>> for Map<K, V> {
+    type Error = Error;
+
+    fn try_from(items: Vec<(K, V)>) -> Result<Self> {
+        let mut args = Vec::with_capacity(2 * items.len());
+        for (key, value) in &items {
+            // string keys are kept as strings to create a string map
+            args.push(key.to_arg_value());
+            args.push(value.to_node_arg()?);
+        }
+        let node = NodeRef::try_from(call_api("_Map", &args)?)?;
+        Ok(Map::from_node(node))
+    }
+}
+
+impl<K, V, S> TryFrom<HashMap<K, V, S>> for Map<K, V>
+where
+    K: ContainerElem + Eq + Hash,
+    V: ContainerElem,
+{
+    type Error = Error;
+
+    fn try_from(items: HashMap<K, V, S>) -> Result<Self> {
+        Map::try_from(items.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<K: ContainerElem, V: FromContainer> FromContainer for Map<K, V> {
+    fn from_ret_value(val: RetValue) -> Result<Self> {
+        Ok(Map::from_node(NodeRef::try_from(val)?))
+    }
+}
+
+impl<K: ContainerElem, V: FromContainer> ContainerElem for Map<K, V> {
+    fn to_arg_value(&self) -> ArgValue {
+        ArgValue::Node(self.node.clone())
+    }
+}
+
+impl<K, V> From<Map<K, V>> for ArgValue {
+    fn from(map: Map<K, V>) -> Self {
+        ArgValue::Node(map.node)
+    }
+}
+
+impl<K: ContainerElem, V: FromContainer> TryFrom<ArgValue> for Map<K, V> {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<Self> {
+        Map::from_ret_value(val)
+    }
+}
+
+impl<'a, K, V> From<&'a Map<K, V>> for TVMArgValue<'a> {
+    fn from(map: &'a Map<K, V>) -> Self {
+        TVMArgValue::from(&map.node)
+    }
+}
+
+// containers are only registered in the full `libtvm`
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array() {
+        let empty = Array::<NodeRef>::try_from(vec![]).unwrap();
+        assert!(empty.is_empty().unwrap());
+        let nested = Array::try_from(vec![empty.clone(), empty]).unwrap();
+        assert_eq!(nested.len().unwrap(), 2);
+        let inner = nested.to_vec().unwrap();
+        assert_eq!(inner.len(), 2);
+        assert_eq!(inner[1].len().unwrap(), 0);
+        assert!(nested.get(2).is_err());
+
+        let names = vec!["x".to_owned(), "y".to_owned()];
+        let strings = Array::try_from_iter(names.iter().cloned()).unwrap();
+        assert_eq!(strings.to_vec().unwrap(), names);
+    }
+
+    #[test]
+    fn map() {
+        let empty = Array::<NodeRef>::try_from(vec![]).unwrap();
+        let mut items = HashMap::new();
+        items.insert("a".to_owned(), empty.clone());
+        items.insert("b".to_owned(), empty);
+        let map = Map::try_from(items).unwrap();
+        assert_eq!(map.len().unwrap(), 2);
+        assert!(map.get(&"a".to_owned()).unwrap().is_empty().unwrap());
+        let items = map.to_hash_map().unwrap();
+        let mut keys: Vec<_> = items.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a".to_owned(), "b".to_owned()]);
+
+        let names = Map::try_from_iter(keys.into_iter().map(|key| (key.clone(), key))).unwrap();
+        assert_eq!(names.get(&"b".to_owned()).unwrap(), "b");
+    }
+}
@@ -1,11 +1,11 @@
 //! Provides [`TVMContext`] and related device specific queries.
 //!
-//! Create a new context by device type (cpu is 1) and device id.
+//! Create a new context by device type and device id.
 //!
 //! # Example
 //!
 //! ```
-//! let ctx = TVMContext::new(1, 0);
+//! let ctx = TVMContext::new(TVMDeviceType::Cpu, 0);
 //! let cpu0 = TVMContext::cpu(0);
 //! assert_eq!(ctx, cpu0);
 //! ```
@@ -13,128 +13,234 @@
 //! Or from a supported device name.
 //!
 //! ```
-//! let cpu0 = TVMContext::from("cpu");
+//! let cpu0: TVMContext = "cpu".parse().unwrap();
 //! println!("{}", cpu0);
+//! let gpu1: TVMContext = "cuda:1".parse().unwrap();
+//! assert_eq!(gpu1, TVMContext::gpu(1));
 //! ```
 
 use std::{
+    convert::TryFrom,
     fmt::{self, Display, Formatter},
     os::raw::c_void,
     ptr,
+    str::FromStr,
 };
 
 use function;
 use internal_api;
 use ts;
+use ArgValue;
+use Error;
+use ErrorKind;
 use Result;
+use RetValue;
 
-/// Device type can be from a supported device name. See the supported devices
-/// in [TVM](https://github.com/dmlc/tvm).
+/// Number of device type codes per RPC session. A device of an RPC session is encoded as
+/// `(session index + 1) * RPC_SESS_MASK + device type`.
+pub const RPC_SESS_MASK: usize = 128;
+
+/// Device type, covering the DLPack devices, the devices added by TVM and the devices
+/// of RPC sessions. See the supported devices in [TVM](https://github.com/dmlc/tvm).
 ///
 /// ## Example
 ///
 /// ```
-/// let cpu = TVMDeviceType::from("cpu");
+/// let cpu: TVMDeviceType = "cpu".parse().unwrap();
 /// println!("device is: {}", cpu);
+/// assert!("tpu".parse::<TVMDeviceType>().is_err());
 ///```
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct TVMDeviceType(pub usize);
+pub enum TVMDeviceType {
+    Cpu,
+    Gpu,
+    CpuPinned,
+    OpenCL,
+    Aocl,
+    SdAccel,
+    Vulkan,
+    Metal,
+    Vpi,
+    Rocm,
+    OpenGL,
+    ExtDev,
+    /// Device of type code `device` in the RPC session of index `session`. The code must be
+    /// less than [`RPC_SESS_MASK`]. The fields are not checked when the variant is built
+    /// directly, and an out of range code is encoded as a device of another session, so
+    /// prefer [`TVMDeviceType::rpc`], which returns an error instead.
+    Rpc {
+        session: usize,
+        device: usize,
+    },
+}
 
 impl Default for TVMDeviceType {
     /// default device is cpu.
     fn default() -> Self {
-        TVMDeviceType(1)
+        TVMDeviceType::Cpu
     }
 }
 
-impl From<TVMDeviceType> for ts::DLDeviceType {
+impl From<TVMDeviceType> for usize {
     fn from(device_type: TVMDeviceType) -> Self {
-        match device_type.0 {
-            1 => ts::DLDeviceType_kDLCPU,
-            2 => ts::DLDeviceType_kDLGPU,
-            3 => ts::DLDeviceType_kDLCPUPinned,
-            4 => ts::DLDeviceType_kDLOpenCL,
-            7 => ts::DLDeviceType_kDLVulkan,
-            8 => ts::DLDeviceType_kDLMetal,
-            9 => ts::DLDeviceType_kDLVPI,
-            10 => ts::DLDeviceType_kDLROCM,
-            12 => ts::DLDeviceType_kDLExtDev,
-            _ => panic!("device type not found!"),
+        match device_type {
+            TVMDeviceType::Cpu => 1,
+            TVMDeviceType::Gpu => 2,
+            TVMDeviceType::CpuPinned => 3,
+            TVMDeviceType::OpenCL => 4,
+            TVMDeviceType::Aocl => 5,
+            TVMDeviceType::SdAccel => 6,
+            TVMDeviceType::Vulkan => 7,
+            TVMDeviceType::Metal => 8,
+            TVMDeviceType::Vpi => 9,
+            TVMDeviceType::Rocm => 10,
+            TVMDeviceType::OpenGL => 11,
+            TVMDeviceType::ExtDev => 12,
+            TVMDeviceType::Rpc { session, device } => (session + 1) * RPC_SESS_MASK + device,
         }
     }
 }
 
-impl From<ts::DLDeviceType> for TVMDeviceType {
-    fn from(device_type: ts::DLDeviceType) -> Self {
-        match device_type {
-            ts::DLDeviceType_kDLCPU => TVMDeviceType(1),
-            ts::DLDeviceType_kDLGPU => TVMDeviceType(2),
-            ts::DLDeviceType_kDLCPUPinned => TVMDeviceType(3),
-            ts::DLDeviceType_kDLOpenCL => TVMDeviceType(4),
-            ts::DLDeviceType_kDLVulkan => TVMDeviceType(7),
-            ts::DLDeviceType_kDLMetal => TVMDeviceType(8),
-            ts::DLDeviceType_kDLVPI => TVMDeviceType(9),
-            ts::DLDeviceType_kDLROCM => TVMDeviceType(10),
-            ts::DLDeviceType_kDLExtDev => TVMDeviceType(12),
-            _ => panic!("device type not found!"),
+impl TryFrom<usize> for TVMDeviceType {
+    type Error = Error;
+
+    fn try_from(code: usize) -> Result<Self> {
+        let device_type = match code {
+            1 => TVMDeviceType::Cpu,
+            2 => TVMDeviceType::Gpu,
+            3 => TVMDeviceType::CpuPinned,
+            4 => TVMDeviceType::OpenCL,
+            5 => TVMDeviceType::Aocl,
+            6 => TVMDeviceType::SdAccel,
+            7 => TVMDeviceType::Vulkan,
+            8 => TVMDeviceType::Metal,
+            9 => TVMDeviceType::Vpi,
+            10 => TVMDeviceType::Rocm,
+            11 => TVMDeviceType::OpenGL,
+            12 => TVMDeviceType::ExtDev,
+            code if code >= RPC_SESS_MASK => {
+                let device = code % RPC_SESS_MASK;
+                // only a known device can be in a session
+                TVMDeviceType::try_from(device)?;
+                TVMDeviceType::rpc(code / RPC_SESS_MASK - 1, device)?
+            }
+            code => bail!(ErrorKind::UnknownDevice(code.to_string())),
+        };
+        Ok(device_type)
+    }
+}
+
+impl TVMDeviceType {
+    /// Creates the device of type code `device` in the RPC session of index `session`.
+    /// Returns an error if `device` does not fit below [`RPC_SESS_MASK`], as its code would
+    /// overflow into the next session.
+    pub fn rpc(session: usize, device: usize) -> Result<Self> {
+        if device >= RPC_SESS_MASK {
+            bail!(ErrorKind::UnknownDevice(format!("remote[{}]:{}", session, device)));
         }
+        Ok(TVMDeviceType::Rpc { session, device })
+    }
+}
+
+impl From<TVMDeviceType> for ts::DLDeviceType {
+    fn from(device_type: TVMDeviceType) -> Self {
+        usize::from(device_type) as ts::DLDeviceType
+    }
+}
+
+impl TryFrom<ts::DLDeviceType> for TVMDeviceType {
+    type Error = Error;
+
+    fn try_from(device_type: ts::DLDeviceType) -> Result<Self> {
+        TVMDeviceType::try_from(device_type as usize)
     }
 }
 
 impl Display for TVMDeviceType {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                TVMDeviceType(1) => "cpu",
-                TVMDeviceType(2) => "gpu",
-                TVMDeviceType(3) => "cpu_pinned",
-                TVMDeviceType(4) => "opencl",
-                TVMDeviceType(8) => "meta",
-                TVMDeviceType(9) => "vpi",
-                TVMDeviceType(10) => "rocm",
-                TVMDeviceType(_) => "rpc",
+        let name = match *self {
+            TVMDeviceType::Cpu => "cpu",
+            TVMDeviceType::Gpu => "gpu",
+            TVMDeviceType::CpuPinned => "cpu_pinned",
+            TVMDeviceType::OpenCL => "opencl",
+            TVMDeviceType::Aocl => "aocl",
+            TVMDeviceType::SdAccel => "sdaccel",
+            TVMDeviceType::Vulkan => "vulkan",
+            TVMDeviceType::Metal => "metal",
+            TVMDeviceType::Vpi => "vpi",
+            TVMDeviceType::Rocm => "rocm",
+            TVMDeviceType::OpenGL => "opengl",
+            TVMDeviceType::ExtDev => "ext_dev",
+            TVMDeviceType::Rpc { session, device } => {
+                return match TVMDeviceType::try_from(device) {
+                    Ok(local) => write!(f, "remote[{}]:{}", session, local),
+                    // keeps the unknown code parsable
+                    Err(_) => write!(f, "remote[{}]:{}", session, device),
+                };
             }
-        )
+        };
+        write!(f, "{}", name)
     }
 }
 
-impl<'a> From<&'a str> for TVMDeviceType {
-    fn from(type_str: &'a str) -> Self {
-        match type_str {
-            "cpu" => TVMDeviceType(1),
-            "llvm" => TVMDeviceType(1),
-            "stackvm" => TVMDeviceType(1),
-            "gpu" => TVMDeviceType(2),
-            "cuda" => TVMDeviceType(2),
-            "nvptx" => TVMDeviceType(2),
-            "cl" => TVMDeviceType(4),
-            "opencl" => TVMDeviceType(4),
-            "metal" => TVMDeviceType(8),
-            "vpi" => TVMDeviceType(9),
-            "rocm" => TVMDeviceType(10),
-            _ => panic!("{:?} not supported!", type_str),
-        }
+impl FromStr for TVMDeviceType {
+    type Err = Error;
+
+    /// Parses a device name, also accepting the target names of TVM such as `llvm` or
+    /// `cuda`, and the `remote[<session>]:<device>` format of RPC session devices.
+    fn from_str(type_str: &str) -> Result<Self> {
+        let device_type = match type_str {
+            "cpu" | "llvm" | "stackvm" | "c" => TVMDeviceType::Cpu,
+            "gpu" | "cuda" | "nvptx" => TVMDeviceType::Gpu,
+            "cpu_pinned" => TVMDeviceType::CpuPinned,
+            "cl" | "opencl" => TVMDeviceType::OpenCL,
+            "aocl" | "aocl_sw_emu" => TVMDeviceType::Aocl,
+            "sdaccel" => TVMDeviceType::SdAccel,
+            "vulkan" => TVMDeviceType::Vulkan,
+            "metal" => TVMDeviceType::Metal,
+            "vpi" => TVMDeviceType::Vpi,
+            "rocm" => TVMDeviceType::Rocm,
+            "opengl" => TVMDeviceType::OpenGL,
+            "ext_dev" => TVMDeviceType::ExtDev,
+            _ => return parse_remote(type_str),
+        };
+        Ok(device_type)
     }
 }
 
+/// Parses `remote[<session>]:<device>`.
+fn parse_remote(type_str: &str) -> Result<TVMDeviceType> {
+    let unknown = || ErrorKind::UnknownDevice(type_str.to_owned());
+    let rest = type_str.strip_prefix("remote[").ok_or_else(unknown)?;
+    let end = rest.find("]:").ok_or_else(unknown)?;
+    let session = rest[..end].parse::<usize>().map_err(|_| unknown())?;
+    let device = &rest[end + 2..];
+    let device = match device.parse::<usize>() {
+        Ok(code) => code,
+        Err(_) => match device.parse::<TVMDeviceType>()? {
+            TVMDeviceType::Rpc { .. } => bail!(unknown()),
+            device => usize::from(device),
+        },
+    };
+    TVMDeviceType::rpc(session, device)
+}
+
 /// Represents the underlying device context. Default is cpu.
 ///
 /// ## Examples
 ///
 /// ```
-/// let ctx = TVMContext::from("gpu");
-/// assert!(ctx.exist());
+/// let ctx: TVMContext = "gpu(0)".parse().unwrap();
+/// assert!(ctx.exist().unwrap());
 ///
 /// ```
 ///
 /// It is possible to query the underlying context as follows
 ///
 /// ```
-/// println!("maximun threads per block: {}", ctx.max_threads_per_block());
-/// println!("compute version: {}", ctx.compute_version());
+/// let attrs = ctx.attributes().unwrap();
+/// println!("maximun threads per block: {:?}", attrs.max_threads_per_block);
+/// println!("compute version: {:?}", attrs.compute_version);
 /// ```
 
 #[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
@@ -156,49 +262,69 @@ impl TVMContext {
 }
 
 macro_rules! impl_ctxs {
-    ($(($ctx:ident, $dldevt:expr));+) => {
+    ($(($ctx:ident, $device_type:ident));+) => {
         $(
             impl TVMContext {
                 pub fn $ctx(device_id: usize) -> Self {
-                    Self::new(TVMDeviceType($dldevt), device_id)
+                    Self::new(TVMDeviceType::$device_type, device_id)
                 }
             }
         )+
     };
 }
 
-impl_ctxs!((cpu, 1);
-            (gpu, 2);
-            (nvptx, 2);
-            (cuda, 2);
-            (cpu_pinned, 3);
-            (cl, 4);
-            (opencl, 4);
-            (metal, 8);
-            (vpi, 9);
-            (rocm, 10));
-
-impl<'a> From<&'a str> for TVMContext {
-    fn from(target: &str) -> Self {
-        TVMContext::new(TVMDeviceType::from(target), 0)
+impl_ctxs!((cpu, Cpu);
+            (gpu, Gpu);
+            (nvptx, Gpu);
+            (cuda, Gpu);
+            (cpu_pinned, CpuPinned);
+            (cl, OpenCL);
+            (opencl, OpenCL);
+            (vulkan, Vulkan);
+            (metal, Metal);
+            (vpi, Vpi);
+            (rocm, Rocm);
+            (opengl, OpenGL);
+            (ext_dev, ExtDev));
+
+impl FromStr for TVMContext {
+    type Err = Error;
+
+    /// Parses a device name with an optional device id as `<device>`, `<device>:<id>` or
+    /// `<device>(<id>)`, which is the `Display` format. Options of a TVM target string
+    /// such as `llvm -mcpu=skylake` are ignored.
+    fn from_str(ctx_str: &str) -> Result<Self> {
+        let invalid = || ErrorKind::UnknownDevice(ctx_str.to_owned());
+        let name = ctx_str.split_whitespace().next().ok_or_else(invalid)?;
+        if let Some(name) = name.strip_suffix(')') {
+            let start = name.rfind('(').ok_or_else(invalid)?;
+            let device_id = name[start + 1..].parse().map_err(|_| invalid())?;
+            let device_type = name[..start].parse().map_err(|_| invalid())?;
+            return Ok(TVMContext::new(device_type, device_id));
+        }
+        if let Some(sep) = name.rfind(':') {
+            // the name of an RPC session device has a colon as well, e.g. `remote[0]:cpu`
+            if let (Ok(device_type), Ok(device_id)) = (name[..sep].parse(), name[sep + 1..].parse())
+            {
+                return Ok(TVMContext::new(device_type, device_id));
+            }
+        }
+        let device_type = name.parse().map_err(|_| invalid())?;
+        Ok(TVMContext::new(device_type, 0))
     }
 }
 
 impl TVMContext {
-    /// Checks whether the context exists or not.
-    pub fn exist(&self) -> bool {
-        let func = internal_api::get_api("_GetDeviceAttr".to_owned());
-        let dt = self.device_type.0 as usize;
-        // `unwrap` is ok here because if there is any error,
-        // if would occure inside `call_packed!`
-        let ret = call_packed!(func, &dt, &self.device_id, &0).unwrap();
-        ret.to_int() != 0
+    /// Checks whether the context exists or not. Returns an error if the device API of the
+    /// context cannot be queried.
+    pub fn exist(&self) -> Result<bool> {
+        Ok(self.usize_attr(DeviceAttrKind::Exist)?.unwrap_or(0) != 0)
     }
 
     /// Synchronize the context stream.
     pub fn sync(&self) -> Result<()> {
         check_call!(ts::TVMSynchronize(
-            self.device_type.0 as i32,
+            usize::from(self.device_type) as i32,
             self.device_id as i32,
             ptr::null_mut() as *mut c_void
         ));
@@ -206,40 +332,182 @@ impl TVMContext {
     }
 }
 
-macro_rules! impl_dev_attrs {
-    ($attr_name:ident, $attr_kind:expr) => {
-        impl TVMContext {
-            pub fn $attr_name(&self) -> usize {
-                let func = ::internal_api::get_api("_GetDeviceAttr".to_owned());
-                let dt = self.device_type.0 as usize;
-                // `unwrap` is ok here because if there is any error,
-                // if would occur in function call.
-                let ret = function::Builder::from(func)
-                    .args(&[dt, self.device_id, $attr_kind])
-                    .invoke()
-                    .unwrap();
-                ret.to_int() as usize
-            }
+/// Kinds of the `_GetDeviceAttr` queries, mirroring `DeviceAttrKind` of TVM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceAttrKind {
+    Exist = 0,
+    MaxThreadsPerBlock = 1,
+    WarpSize = 2,
+    MaxSharedMemoryPerBlock = 3,
+    ComputeVersion = 4,
+    DeviceName = 5,
+    MaxClockRate = 6,
+    MultiProcessorCount = 7,
+    MaxThreadDimensions = 8,
+}
+
+/// Attributes of a device. An attribute which is not supported by the device API,
+/// e.g. the warp size of a cpu, is `None`.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct DeviceAttributes {
+    pub max_threads_per_block: Option<usize>,
+    pub warp_size: Option<usize>,
+    pub max_shared_memory_per_block: Option<usize>,
+    /// Compute version, e.g. `"7.0"` for cuda.
+    pub compute_version: Option<String>,
+    pub device_name: Option<String>,
+    /// Maximum clock rate in kHz.
+    pub max_clock_rate: Option<usize>,
+    pub multi_processor_count: Option<usize>,
+    /// Maximum thread block dimensions as `[x, y, z]`.
+    pub max_thread_dimensions: Option<[usize; 3]>,
+}
+
+impl TVMContext {
+    fn device_attr(&self, kind: DeviceAttrKind) -> Result<RetValue> {
+        let func = internal_api::get_api("_GetDeviceAttr".to_owned());
+        func.call(&[
+            ArgValue::from(usize::from(self.device_type)),
+            ArgValue::from(self.device_id),
+            ArgValue::from(kind as i32),
+        ])
+    }
+
+    fn usize_attr(&self, kind: DeviceAttrKind) -> Result<Option<usize>> {
+        match self.device_attr(kind)? {
+            RetValue::Null => Ok(None),
+            ret => Ok(Some(i64::try_from(ret)? as usize)),
+        }
+    }
+
+    fn string_attr(&self, kind: DeviceAttrKind) -> Result<Option<String>> {
+        match self.device_attr(kind)? {
+            RetValue::Null => Ok(None),
+            ret => Ok(Some(String::try_from(ret)?)),
+        }
+    }
+
+    /// Queries all the attributes of the device. Returns an error if the device does not exist.
+    pub fn attributes(&self) -> Result<DeviceAttributes> {
+        if !self.exist()? {
+            bail!(ErrorKind::UnknownDevice(self.to_string()));
         }
+        let max_thread_dimensions = match self.string_attr(DeviceAttrKind::MaxThreadDimensions)? {
+            Some(dims) => Some(parse_thread_dimensions(&dims)?),
+            None => None,
+        };
+        Ok(DeviceAttributes {
+            max_threads_per_block: self.usize_attr(DeviceAttrKind::MaxThreadsPerBlock)?,
+            warp_size: self.usize_attr(DeviceAttrKind::WarpSize)?,
+            max_shared_memory_per_block: self
+                .usize_attr(DeviceAttrKind::MaxSharedMemoryPerBlock)?,
+            compute_version: self.string_attr(DeviceAttrKind::ComputeVersion)?,
+            device_name: self.string_attr(DeviceAttrKind::DeviceName)?,
+            max_clock_rate: self.usize_attr(DeviceAttrKind::MaxClockRate)?,
+            multi_processor_count: self.usize_attr(DeviceAttrKind::MultiProcessorCount)?,
+            max_thread_dimensions,
+        })
+    }
+}
+
+/// Parses the thread dimensions formatted as `[x, y, z]`.
+fn parse_thread_dimensions(dims_str: &str) -> Result<[usize; 3]> {
+    let mismatch = || {
+        ErrorKind::TypeMismatch(
+            "thread dimensions `[x, y, z]`".to_owned(),
+            format!("`{}`", dims_str),
+        )
     };
+    let inner = dims_str
+        .trim()
+        .strip_prefix('[')
+        .and_then(|dims| dims.strip_suffix(']'))
+        .ok_or_else(mismatch)?;
+    let mut dims = [0; 3];
+    let mut num_dims = 0;
+    for dim in inner.split(',') {
+        if num_dims == dims.len() {
+            bail!(mismatch());
+        }
+        dims[num_dims] = dim.trim().parse().map_err(|_| mismatch())?;
+        num_dims += 1;
+    }
+    if num_dims != dims.len() {
+        bail!(mismatch());
+    }
+    Ok(dims)
 }
 
-impl_dev_attrs!(max_threads_per_block, 1);
-impl_dev_attrs!(warp_size, 2);
-impl_dev_attrs!(max_shared_memory_per_block, 3);
-impl_dev_attrs!(compute_version, 4);
-impl_dev_attrs!(device_name, 5);
-impl_dev_attrs!(max_clock_rate, 6);
-impl_dev_attrs!(multi_processor_count, 7);
-impl_dev_attrs!(max_thread_dimensions, 8);
-
-impl From<ts::DLContext> for TVMContext {
-    fn from(ctx: ts::DLContext) -> Self {
-        TVMContext {
-            device_type: TVMDeviceType::from(ctx.device_type),
-            device_id: ctx.device_id as usize,
+/// Maximum number of devices of a device type probed by [`devices`].
+pub const MAX_DEVICES_PER_TYPE: usize = 32;
+
+/// Device present at runtime.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Device {
+    pub ctx: TVMContext,
+    pub attributes: DeviceAttributes,
+}
+
+/// Returns the devices present at runtime in the order of their device type codes,
+/// i.e. starting with the cpu, by probing the device APIs TVM is compiled with.
+///
+/// ## Example
+///
+/// ```
+/// let best = tvm::devices()
+///     .unwrap()
+///     .into_iter()
+///     .map(|device| device.ctx)
+///     .find(|ctx| ctx.device_type == TVMDeviceType::Gpu)
+///     .unwrap_or_else(|| TVMContext::cpu(0));
+/// ```
+pub fn devices() -> Result<Vec<Device>> {
+    let local_device_types = [
+        TVMDeviceType::Cpu,
+        TVMDeviceType::Gpu,
+        TVMDeviceType::OpenCL,
+        TVMDeviceType::Aocl,
+        TVMDeviceType::SdAccel,
+        TVMDeviceType::Vulkan,
+        TVMDeviceType::Metal,
+        TVMDeviceType::Vpi,
+        TVMDeviceType::Rocm,
+        TVMDeviceType::OpenGL,
+        TVMDeviceType::ExtDev,
+    ];
+    let mut devices = Vec::new();
+    for &device_type in local_device_types.iter() {
+        // device APIs are registered as `device_api.<device name>` when compiled in
+        let device_api = format!("device_api.{}", device_type);
+        if function::Function::get_function(&device_api, true).is_none() {
+            continue;
+        }
+        // the cpu device API reports every device id as existing
+        let num_probes = match device_type {
+            TVMDeviceType::Cpu => 1,
+            _ => MAX_DEVICES_PER_TYPE,
+        };
+        for device_id in 0..num_probes {
+            let ctx = TVMContext::new(device_type, device_id);
+            if !ctx.exist()? {
+                break;
+            }
+            let attributes = ctx.attributes()?;
+            devices.push(Device { ctx, attributes });
         }
     }
+    Ok(devices)
+}
+
+impl TryFrom<ts::DLContext> for TVMContext {
+    type Error = Error;
+
+    fn try_from(ctx: ts::DLContext) -> Result<Self> {
+        Ok(TVMContext {
+            device_type: TVMDeviceType::try_from(ctx.device_type)?,
+            device_id: ctx.device_id as usize,
+        })
+    }
 }
 
 impl From<TVMContext> for ts::DLContext {
@@ -265,13 +533,139 @@ mod tests {
     fn context() {
         let ctx = TVMContext::cpu(0);
         println!("ctx: {}", ctx);
-        let default_ctx = TVMContext::new(TVMDeviceType(1), 0);
+        let default_ctx = TVMContext::new(TVMDeviceType::Cpu, 0);
         assert_eq!(ctx.clone(), default_ctx);
         assert_ne!(ctx, TVMContext::gpu(0));
 
-        let str_ctx = TVMContext::new(TVMDeviceType::from("gpu"), 0);
+        let str_ctx = TVMContext::new("gpu".parse().unwrap(), 0);
         assert_eq!(str_ctx.clone(), str_ctx);
-        assert_ne!(str_ctx, TVMContext::new(TVMDeviceType::from("cpu"), 0));
+        assert_ne!(str_ctx, TVMContext::new("cpu".parse().unwrap(), 0));
+
+        let raw = ts::DLContext::from(TVMContext::opencl(1));
+        assert_eq!(TVMContext::try_from(raw).unwrap(), TVMContext::opencl(1));
+        let unknown = ts::DLContext {
+            device_type: 13 as ts::DLDeviceType,
+            device_id: 0,
+        };
+        assert!(TVMContext::try_from(unknown).is_err());
+    }
+
+    #[test]
+    fn device_type() {
+        let device_types = [
+            TVMDeviceType::Cpu,
+            TVMDeviceType::Gpu,
+            TVMDeviceType::CpuPinned,
+            TVMDeviceType::OpenCL,
+            TVMDeviceType::Aocl,
+            TVMDeviceType::SdAccel,
+            TVMDeviceType::Vulkan,
+            TVMDeviceType::Metal,
+            TVMDeviceType::Vpi,
+            TVMDeviceType::Rocm,
+            TVMDeviceType::OpenGL,
+            TVMDeviceType::ExtDev,
+            TVMDeviceType::Rpc {
+                session: 0,
+                device: 4,
+            },
+            TVMDeviceType::Rpc {
+                session: 3,
+                device: 1,
+            },
+        ];
+        for &device_type in device_types.iter() {
+            let name = device_type.to_string();
+            assert_eq!(name.parse::<TVMDeviceType>().unwrap(), device_type);
+            let code = usize::from(device_type);
+            assert_eq!(TVMDeviceType::try_from(code).unwrap(), device_type);
+        }
+        assert_eq!(TVMDeviceType::Metal.to_string(), "metal");
+        assert_eq!(
+            usize::from(TVMDeviceType::Rpc {
+                session: 0,
+                device: 1
+            }),
+            129
+        );
+        assert_eq!(
+            "remote[1]:opencl".parse::<TVMDeviceType>().unwrap(),
+            TVMDeviceType::try_from(2 * RPC_SESS_MASK + 4).unwrap()
+        );
+        assert_eq!("llvm".parse::<TVMDeviceType>().unwrap(), TVMDeviceType::Cpu);
+        assert_eq!("cuda".parse::<TVMDeviceType>().unwrap(), TVMDeviceType::Gpu);
+        for name in [
+            "",
+            "tpu",
+            "CPU",
+            "remote[]:cpu",
+            "remote[0]:tpu",
+            "remote[0]:remote[1]:cpu",
+        ]
+        .iter()
+        {
+            assert!(name.parse::<TVMDeviceType>().is_err(), "{}", name);
+        }
+        for &code in [0, 13, 127, RPC_SESS_MASK, RPC_SESS_MASK + 13].iter() {
+            assert!(TVMDeviceType::try_from(code).is_err(), "{}", code);
+        }
+        assert_eq!(
+            TVMDeviceType::rpc(2, 4).unwrap(),
+            TVMDeviceType::Rpc {
+                session: 2,
+                device: 4
+            }
+        );
+        assert!(TVMDeviceType::rpc(0, RPC_SESS_MASK + 1).is_err());
+        assert!("remote[0]:129".parse::<TVMDeviceType>().is_err());
+    }
+
+    #[test]
+    fn parse_context() {
+        let parse = |ctx: &str| ctx.parse::<TVMContext>().unwrap();
+        assert_eq!(parse("cpu"), TVMContext::cpu(0));
+        assert_eq!(parse("cpu:0"), TVMContext::cpu(0));
+        assert_eq!(parse("cpu(0)"), TVMContext::cpu(0));
+        assert_eq!(parse("opencl:2"), TVMContext::opencl(2));
+        assert_eq!(parse("cuda:1"), TVMContext::gpu(1));
+        assert_eq!(parse("llvm -mcpu=skylake"), TVMContext::cpu(0));
+        assert_eq!(parse(" cuda -libs=cudnn"), TVMContext::gpu(0));
+        let remote_gpu = TVMDeviceType::Rpc {
+            session: 1,
+            device: 2,
+        };
+        let remote = TVMContext::new(remote_gpu, 3);
+        assert_eq!(parse("remote[1]:gpu:3"), remote);
+        assert_eq!(parse("remote[1]:gpu"), TVMContext::new(remote_gpu, 0));
+        for ctx in [TVMContext::vulkan(1), remote].iter() {
+            assert_eq!(&parse(&ctx.to_string()), ctx);
+        }
+        for ctx in [
+            "", "tpu", "cpu:", "cpu:-1", "cpu(0", "cpu(x)", ":0", "(0)", "cpu:0:0",
+        ]
+        .iter()
+        {
+            assert!(ctx.parse::<TVMContext>().is_err(), "{}", ctx);
+        }
+    }
+
+    #[test]
+    fn thread_dimensions() {
+        assert_eq!(
+            parse_thread_dimensions("[1024, 1024, 64]").unwrap(),
+            [1024, 1024, 64]
+        );
+        assert_eq!(parse_thread_dimensions(" [1,2,3] ").unwrap(), [1, 2, 3]);
+        for dims in ["", "[]", "[1, 2]", "[1, 2, 3, 4]", "1, 2, 3", "[1, x, 3]"].iter() {
+            assert!(parse_thread_dimensions(dims).is_err(), "{}", dims);
+        }
+    }
+
+    #[test]
+    fn list_devices() {
+        let devices = devices().unwrap();
+        assert_eq!(devices[0].ctx, TVMContext::cpu(0));
+        assert!(devices.iter().all(|device| device.ctx.exist().unwrap()));
     }
 
     #[test]
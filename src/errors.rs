@@ -1,8 +1,10 @@
 //! This module implements TVM custom [`Error`], [`ErrorKind`] and [`Result`] types.
 
-use std::{ffi, option};
+use std::{ffi, io};
 
 use rust_ndarray;
+use serde_json;
+use zip;
 
 error_chain!{
     errors {
@@ -25,9 +27,69 @@ error_chain!{
             display("expected type `{}`, but found `{}`", expected, found)
         }
 
+        ValueOutOfRange(ty: String, value: String) {
+            description("value out of range")
+            display("value `{}` is out of the range of `{}`", value, ty)
+        }
+
         MissingShapeError {
             description("ndarray `shape()` returns `None`")
-            display("called `Option::unwrap()` on a `None` value")
+            display("ndarray shape is not available")
+        }
+
+        InvalidModulePath(path: String) {
+            description("invalid module path")
+            display("cannot load module from `{}`, expected a UTF-8 path with an extension", path)
+        }
+
+        TVMError(msg: String) {
+            description("error raised by TVM")
+            display("{}", msg)
+        }
+
+        InvalidFormat(msg: String) {
+            description("invalid serialized format")
+            display("invalid serialized format: {}", msg)
+        }
+
+        ArgumentCountMismatch(func: String, expected: usize, found: usize) {
+            description("argument count mismatch")
+            display("`{}` expects {} arguments, but found {}", func, expected, found)
+        }
+
+        InvalidArgument(func: String, arg: String, reason: String) {
+            description("invalid argument")
+            display("invalid argument `{}` of `{}`: {}", arg, func, reason)
+        }
+
+        UnknownTypeCode(code: i32) {
+            description("unknown type code")
+            display("unknown type code `{}`", code)
+        }
+
+        InvalidExtType(code: i32, reason: String) {
+            description("invalid extension type")
+            display("invalid extension type `{}`: {}", code, reason)
+        }
+
+        UnknownDevice(device: String) {
+            description("unknown device")
+            display("unknown device `{}`", device)
+        }
+
+        MissingGlobalFunction(name: String) {
+            description("missing global function")
+            display("global function `{}` is not registered, TVM may be built without it", name)
+        }
+
+        UnknownNode(name: String) {
+            description("unknown graph node")
+            display("graph has no node named `{}`", name)
+        }
+
+        RpcError(msg: String) {
+            description("RPC error")
+            display("RPC error: {}", msg)
         }
 
     }
@@ -36,11 +98,9 @@ error_chain!{
         ShapeError(rust_ndarray::ShapeError);
         NulError(ffi::NulError);
         IntoStringError(ffi::IntoStringError);
+        IoError(io::Error);
+        ZipError(zip::result::ZipError);
+        JsonError(serde_json::Error);
     }
 }
 
-impl From<option::NoneError> for Error {
-    fn from(_err: option::NoneError) -> Self {
-        ErrorKind::MissingShapeError.into()
-    }
-}
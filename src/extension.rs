@@ -0,0 +1,280 @@
+//! This module provides extension types, which pass Rust values as opaque handles
+//! through TVM packed functions.
+//!
+//! A type implementing [`ExtType`] is registered once with [`register`] and then wrapped
+//! in an [`ExtValue`] owning it. Dropping the value calls the free hook of its type code,
+//! which is the `Drop` of the Rust type, or `TVMExtTypeFree` for the extension types
+//! registered in C++.
+//!
+//! *Note:* TVM cannot copy a Rust extension value, so it can be passed as an argument,
+//! for example to a registered Rust function borrowing it with [`TVMArgValue::to_ext`],
+//! but not returned from a registered Rust function.
+//!
+//! # Example
+//!
+//! ```
+//! struct Counter(usize);
+//!
+//! impl ExtType for Counter {
+//!     const TYPE_CODE: i32 = extension::EXT_RESERVE_END;
+//! }
+//!
+//! extension::register::<Counter>().unwrap();
+//! let counter = ExtValue::new(Counter(0)).unwrap();
+//! call_packed!(count_fn, &counter).unwrap();
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    mem,
+    os::raw::{c_int, c_void},
+    sync::Mutex,
+};
+
+use ts;
+
+use ErrorKind;
+use Result;
+
+/// First extension type code.
+pub const EXT_BEGIN: i32 = ts::TVMTypeCode_kExtBegin as i32;
+/// First extension type code which is not reserved for TVM, i.e. available to Rust types.
+pub const EXT_RESERVE_END: i32 = ts::TVMTypeCode_kExtReserveEnd as i32;
+/// End (exclusive) of the extension type codes.
+pub const EXT_END: i32 = ts::TVMTypeCode_kExtEnd as i32;
+
+/// Rust type which can be passed through packed functions as an extension type.
+pub trait ExtType: Any {
+    /// Type code of the extension type, between `EXT_RESERVE_END` and `EXT_END`.
+    const TYPE_CODE: i32;
+}
+
+#[derive(Clone, Copy)]
+struct ExtEntry {
+    type_id: TypeId,
+    free: unsafe fn(*mut c_void),
+}
+
+lazy_static! {
+    static ref EXT_TYPES: Mutex<HashMap<i32, ExtEntry>> = Mutex::new(HashMap::new());
+}
+
+unsafe fn free_boxed<T>(handle: *mut c_void) {
+    mem::drop(Box::from_raw(handle as *mut T));
+}
+
+fn entry(type_code: i32) -> Option<ExtEntry> {
+    EXT_TYPES.lock().unwrap().get(&type_code).cloned()
+}
+
+/// Registers the extension type `T` with its free hook. Registering a type again is a no-op.
+pub fn register<T: ExtType>() -> Result<()> {
+    let code = T::TYPE_CODE;
+    if !(EXT_RESERVE_END..EXT_END).contains(&code) {
+        bail!(ErrorKind::InvalidExtType(
+            code,
+            format!(
+                "Rust type codes must be in [{}, {})",
+                EXT_RESERVE_END, EXT_END
+            )
+        ));
+    }
+    let mut types = EXT_TYPES.lock().unwrap();
+    let type_id = TypeId::of::<T>();
+    match types.get(&code) {
+        Some(entry) if entry.type_id != type_id => bail!(ErrorKind::InvalidExtType(
+            code,
+            "already registered for another type".to_owned()
+        )),
+        _ => (),
+    }
+    types.insert(
+        code,
+        ExtEntry {
+            type_id,
+            free: free_boxed::<T>,
+        },
+    );
+    Ok(())
+}
+
+/// Returns true if `type_code` is registered for a Rust type.
+pub fn is_registered(type_code: i32) -> bool {
+    entry(type_code).is_some()
+}
+
+/// Borrows the value of type `T` behind an extension `handle`.
+pub(crate) unsafe fn downcast<'a, T: ExtType>(
+    handle: *mut c_void,
+    type_code: i32,
+) -> Result<&'a T> {
+    match entry(type_code) {
+        Some(entry) if type_code == T::TYPE_CODE && entry.type_id == TypeId::of::<T>() => {
+            Ok(&*(handle as *const T))
+        }
+        _ => bail!(ErrorKind::TypeMismatch(
+            format!("extension type {}", T::TYPE_CODE),
+            format!("extension type {}", type_code)
+        )),
+    }
+}
+
+/// Value of an extension type.
+#[derive(Debug)]
+pub struct ExtValue {
+    pub(crate) handle: *mut c_void,
+    type_code: i32,
+    is_released: bool,
+}
+
+impl ExtValue {
+    /// Moves `value` of a registered extension type into a new `ExtValue`.
+    pub fn new<T: ExtType>(value: T) -> Result<Self> {
+        match entry(T::TYPE_CODE) {
+            Some(entry) if entry.type_id == TypeId::of::<T>() => (),
+            _ => bail!(ErrorKind::InvalidExtType(
+                T::TYPE_CODE,
+                "not registered for this type".to_owned()
+            )),
+        }
+        let handle = Box::into_raw(Box::new(value)) as *mut c_void;
+        Ok(ExtValue::from_raw(handle, T::TYPE_CODE, false))
+    }
+
+    pub(crate) fn from_raw(handle: *mut c_void, type_code: i32, is_released: bool) -> Self {
+        ExtValue {
+            handle,
+            type_code,
+            is_released,
+        }
+    }
+
+    /// Returns the underlying handle.
+    pub fn handle(&self) -> *mut c_void {
+        self.handle
+    }
+
+    /// Returns the extension type code.
+    pub fn type_code(&self) -> i32 {
+        self.type_code
+    }
+
+    /// Returns true if the underlying value is not freed by this `ExtValue`,
+    /// i.e. it is borrowed, and false otherwise.
+    pub fn is_released(&self) -> bool {
+        self.is_released
+    }
+
+    /// Borrows the value if it is of type `T`.
+    pub fn downcast_ref<T: ExtType>(&self) -> Result<&T> {
+        unsafe { downcast(self.handle, self.type_code) }
+    }
+
+    /// Moves the value out if it is of type `T` and owned by this `ExtValue`.
+    pub fn into_inner<T: ExtType>(self) -> Result<T> {
+        self.downcast_ref::<T>()?;
+        if self.is_released {
+            bail!(ErrorKind::InvalidExtType(
+                self.type_code,
+                "cannot move out of a borrowed value".to_owned()
+            ));
+        }
+        let value = unsafe { Box::from_raw(self.handle as *mut T) };
+        mem::forget(self);
+        Ok(*value)
+    }
+}
+
+impl Drop for ExtValue {
+    fn drop(&mut self) {
+        if self.is_released {
+            return;
+        }
+        match entry(self.type_code) {
+            Some(entry) => unsafe { (entry.free)(self.handle) },
+            None => check_call!(ts::TVMExtTypeFree(self.handle, self.type_code as c_int)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        convert::TryFrom,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use ty::TypeCode;
+    use ArgValue;
+    use TVMArgValue;
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counter(usize);
+
+    impl ExtType for Counter {
+        const TYPE_CODE: i32 = EXT_RESERVE_END;
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Other;
+
+    impl ExtType for Other {
+        const TYPE_CODE: i32 = EXT_RESERVE_END;
+    }
+
+    struct Reserved;
+
+    impl ExtType for Reserved {
+        const TYPE_CODE: i32 = EXT_BEGIN;
+    }
+
+    #[test]
+    fn register_and_free() {
+        register::<Counter>().unwrap();
+        register::<Counter>().unwrap();
+        assert!(register::<Other>().is_err());
+        assert!(register::<Reserved>().is_err());
+        assert!(ExtValue::new(Other).is_err());
+
+        let drops = DROPS.load(Ordering::SeqCst);
+        let val = ArgValue::from(ExtValue::new(Counter(7)).unwrap());
+        assert_eq!(val.type_code(), TypeCode::kExt(EXT_RESERVE_END));
+        {
+            let arg = val.to_raw().unwrap();
+            assert_eq!(arg.to_ext::<Counter>().unwrap().0, 7);
+            assert!(arg.to_ext::<Reserved>().is_err());
+            // a borrowed copy does not free the value
+            assert!(arg.to_owned_value().unwrap().to_raw().is_ok());
+        }
+        assert_eq!(DROPS.load(Ordering::SeqCst), drops);
+        let counter = ExtValue::try_from(val).unwrap();
+        assert_eq!(
+            TVMArgValue::from(&counter).to_ext::<Counter>().unwrap().0,
+            7
+        );
+        assert_eq!(counter.into_inner::<Counter>().unwrap().0, 7);
+        assert_eq!(DROPS.load(Ordering::SeqCst), drops + 1);
+        mem::drop(ExtValue::new(Counter(1)).unwrap());
+        assert_eq!(DROPS.load(Ordering::SeqCst), drops + 2);
+    }
+
+    #[test]
+    fn type_codes() {
+        assert_eq!(TypeCode::try_from(13).unwrap(), TypeCode::kNDArrayContainer);
+        assert_eq!(
+            TypeCode::try_from(EXT_BEGIN).unwrap(),
+            TypeCode::kExt(EXT_BEGIN)
+        );
+        assert_eq!(i32::from(TypeCode::kExt(EXT_END - 1)), EXT_END - 1);
+        assert!(TypeCode::try_from(14).is_err());
+        assert!(TypeCode::try_from(EXT_END).is_err());
+        assert!(TypeCode::try_from(-1).is_err());
+    }
+}
@@ -1,14 +1,17 @@
 //! This module provides an idiomatic Rust API for creating and working with TVM functions.
 //!
 //! For calling an already registered TVM function use [`function::Builder`]
+//! or [`Function::call`] with a slice of [`ArgValue`]s.
 //! To register a TVM packed function from Rust side either
 //! use [`function::register`] or the macro [`register_global_func`].
 //!
 //! See the tests and examples repository for more examples.
 
 use std::{
+    cell::RefCell,
+    convert::TryFrom,
     ffi::{CStr, CString},
-    mem,
+    fmt, mem,
     os::raw::{c_char, c_int, c_void},
     ptr, slice, str,
     sync::Mutex,
@@ -16,13 +19,17 @@ use std::{
 
 use ts;
 
+use extension;
 use ty::TypeCode;
-use value::{TVMValue, ValueKind};
+use value::TVMValue;
+use ArgValue;
 use ErrorKind;
 use Module;
 use Result;
+use RetValue;
 use TVMArgValue;
 use TVMRetValue;
+use ToArgs;
 
 lazy_static! {
     static ref GLOBAL_FUNCTION_NAMES: Mutex<Vec<&'static str>> = {
@@ -52,7 +59,6 @@ pub fn get_global_func(name: &str, is_global: bool) -> Option<Function> {
         &mut handle as *mut _
     ));
     if !(handle.is_null()) {
-        mem::forget(name);
         return Some(Function::new(handle, is_global, false));
     } else {
         None
@@ -139,26 +145,153 @@ impl Drop for Function {
     }
 }
 
+/// Raw argument values and type codes of a packed function call.
+///
+/// The buffers are cleared and refilled for every call so their capacity is reused.
+#[derive(Clone, Default)]
+pub(crate) struct CallFrame {
+    values: Vec<ts::TVMValue>,
+    tcodes: Vec<c_int>,
+}
+
+impl CallFrame {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        CallFrame {
+            values: Vec::with_capacity(capacity),
+            tcodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+        self.tcodes.clear();
+    }
+
+    pub(crate) fn push(&mut self, arg: &TVMArgValue) {
+        self.values.push(arg.value.inner);
+        self.tcodes.push(i32::from(arg.type_code) as c_int);
+    }
+
+    /// Calls the function with the pushed values. The values must still be alive.
+    ///
+    /// An error raised by the callee is returned with the message of TVM.
+    pub(crate) fn call(&mut self, handle: ts::TVMFunctionHandle) -> Result<RetValue> {
+        let mut ret_val = ts::TVMValue { v_int64: 0 };
+        let mut ret_type_code = 0 as c_int;
+        let status = unsafe {
+            ts::TVMFuncCall(
+                handle,
+                self.values.as_mut_ptr(),
+                self.tcodes.as_mut_ptr(),
+                self.values.len() as c_int,
+                &mut ret_val as *mut _,
+                &mut ret_type_code as *mut _,
+            )
+        };
+        if status != 0 {
+            bail!(ErrorKind::TVMError(::get_last_error().to_owned()));
+        }
+        let ret_type_code = TypeCode::try_from(ret_type_code)?;
+        unsafe { RetValue::from_raw(ret_val, ret_type_code) }
+    }
+}
+
+impl fmt::Debug for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallFrame")
+            .field("len", &self.values.len())
+            .field("capacity", &self.values.capacity())
+            .field("tcodes", &self.tcodes)
+            .finish()
+    }
+}
+
+thread_local! {
+    // scratch frame of `Function::call`, a nested call made from a callback gets its own frame.
+    static CALL_FRAME: RefCell<CallFrame> = RefCell::new(CallFrame::with_capacity(8));
+}
+
+impl Function {
+    /// Calls the function with the given arguments.
+    ///
+    /// Apart from copying string and byte array arguments, this reuses a per-thread
+    /// call frame and does not allocate.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// let func = Function::get_function("mysum", true).unwrap();
+    /// let ret = func.call(&[ArgValue::from(10), ArgValue::from(20)]).unwrap();
+    /// assert_eq!(ret.to_int(), 30);
+    /// ```
+    pub fn call(&self, args: &[ArgValue]) -> Result<RetValue> {
+        // only string and byte array arguments own temporaries, so this rarely allocates.
+        let mut temps = Vec::new();
+        CALL_FRAME.with(|frame| {
+            let mut nested = CallFrame::default();
+            let mut borrowed = frame.try_borrow_mut();
+            let frame = match borrowed {
+                Ok(ref mut frame) => &mut **frame,
+                Err(_) => &mut nested,
+            };
+            frame.clear();
+            for arg in args {
+                let raw = arg.to_raw()?;
+                frame.push(&raw);
+                if raw.has_storage() {
+                    temps.push(raw);
+                }
+            }
+            frame.call(self.handle)
+        })
+    }
+}
+
 /// Function builder in order to create and call functions.
 ///
+/// A builder is a reusable call frame: after [`invoke`](#method.invoke) the arguments are
+/// released while the buffers are kept, so the same builder can be refilled for the next call.
+///
 /// *Note:* Currently TVM functions accept *at most* one return value.
+///
+/// ## Example
+///
+/// ```
+/// let mut run = function::Builder::with_capacity(func, 2);
+/// for input in inputs.iter() {
+///     run.arg(input).set_output(&mut output).invoke().unwrap();
+/// }
+/// ```
 #[derive(Debug, Clone, Default)]
 pub struct Builder<'a> {
     pub func: Option<Function>,
-    pub arg_buf: Option<Box<[TVMArgValue<'a>]>>,
-    pub ret_buf: Option<Box<[TVMRetValue]>>,
+    pub arg_buf: Vec<TVMArgValue<'a>>,
+    pub ret_buf: Option<TVMArgValue<'a>>,
+    frame: CallFrame,
 }
 
 impl<'a> Builder<'a> {
     pub fn new(
         func: Option<Function>,
-        arg_buf: Option<Box<[TVMArgValue<'a>]>>,
-        ret_buf: Option<Box<[TVMRetValue]>>,
+        arg_buf: Vec<TVMArgValue<'a>>,
+        ret_buf: Option<TVMArgValue<'a>>,
     ) -> Self {
+        let frame = CallFrame::with_capacity(arg_buf.len() + 1);
         Self {
             func,
             arg_buf,
             ret_buf,
+            frame,
+        }
+    }
+
+    /// Creates a builder for `func` with room for `capacity` arguments.
+    pub fn with_capacity(func: Function, capacity: usize) -> Self {
+        Self {
+            func: Some(func),
+            arg_buf: Vec::with_capacity(capacity),
+            ret_buf: None,
+            frame: CallFrame::with_capacity(capacity + 1),
         }
     }
 
@@ -168,24 +301,14 @@ impl<'a> Builder<'a> {
     }
 
     /// Pushes a [`TVMArgValue`] into the function argument buffer.
+    ///
+    /// Strings and byte arrays are copied into the buffer, which owns them until the
+    /// end of the next [`invoke`](#method.invoke).
     pub fn arg<'b, T: ?Sized>(&mut self, arg: &'b T) -> &mut Self
     where
-        TVMValue: From<&'b T>,
-        TypeCode: From<&'b T>,
+        TVMArgValue<'a>: From<&'b T>,
     {
-        let tvm_arg = TVMArgValue::from(arg);
-        if self.arg_buf.is_none() {
-            self.arg_buf = Some(Box::new([tvm_arg]));
-        } else {
-            let new_arg_buf = self.arg_buf.take().map(|bbuf| {
-                let mut new_arg_buf = Vec::from(bbuf);
-                new_arg_buf.push(tvm_arg);
-                let new_len = new_arg_buf.len();
-                new_arg_buf.truncate(new_len);
-                new_arg_buf.into_boxed_slice()
-            });
-            self.arg_buf = new_arg_buf;
-        }
+        self.arg_buf.push(TVMArgValue::from(arg));
         self
     }
 
@@ -193,93 +316,59 @@ impl<'a> Builder<'a> {
     pub fn args<'b, T: 'b + ?Sized, I>(&mut self, args: I) -> &mut Self
     where
         I: IntoIterator<Item = &'b T>,
-        TVMValue: From<&'b T>,
-        TypeCode: From<&'b T>,
+        TVMArgValue<'a>: From<&'b T>,
     {
         for arg in args {
-            self.arg(&arg);
+            self.arg(arg);
         }
         self
     }
 
+    /// Pushes the fields of a [`ToArgs`] type, such as a struct deriving it, in order.
+    pub fn args_from<T: ToArgs>(&mut self, args: &'a T) -> &mut Self {
+        self.arg_buf.extend(args.to_args());
+        self
+    }
+
     /// Sets an output for a function that requirs a mutable output to be provided.
     /// See the `basics` in tests for an example.
     pub fn set_output<'b, T: 'b + ?Sized>(&mut self, arg: &'b mut T) -> &mut Self
     where
-        TVMValue: From<&'b T>,
-        TypeCode: From<&'b T>,
+        TVMArgValue<'a>: From<&'b T>,
     {
-        let tvm_ret = TVMRetValue::new(TVMValue::from(arg), TypeCode::from(arg));
-        if self.ret_buf.is_none() {
-            self.ret_buf = Some(Box::new([tvm_ret]));
-        } else {
-            let new_ret_buf = self.ret_buf.take().map(|_| {
-                let mut new_buf = Vec::with_capacity(1);
-                new_buf.push(tvm_ret);
-                new_buf.into_boxed_slice()
-            });
-            self.ret_buf = new_ret_buf;
-        }
+        self.ret_buf = Some(TVMArgValue::from(&*arg));
         self
     }
 
-    /// Calls the function that created from `Builder`.
-    pub fn invoke(&mut self) -> Result<TVMRetValue> {
-        self.clone()(())
+    /// Releases the arguments and the output without calling the function.
+    /// The allocated buffers are kept for refilling the builder.
+    pub fn clear(&mut self) -> &mut Self {
+        self.arg_buf.clear();
+        self.ret_buf = None;
+        self.frame.clear();
+        self
     }
-}
 
-impl<'a> FnOnce<((),)> for Builder<'a> {
-    type Output = Result<TVMRetValue>;
-    extern "rust-call" fn call_once(self, _: ((),)) -> Self::Output {
-        if self.func.is_none() {
-            bail!("{}", ErrorKind::FunctionNotFound);
-        }
-        let mut ret_val = unsafe { mem::uninitialized::<ts::TVMValue>() };
-        let mut ret_type_code = 0 as c_int;
-        if self.arg_buf.is_some() {
-            let arg_buf = self.arg_buf?;
-            let mut num_args = arg_buf.len();
-            let mut values = arg_buf
-                .iter()
-                .map(|tav| tav.value.inner)
-                .collect::<Vec<ts::TVMValue>>();
-            let mut tcodes = arg_buf
-                .iter()
-                .map(|tav| tav.type_code as c_int)
-                .collect::<Vec<_>>();
-            if self.ret_buf.is_some() {
-                num_args = num_args + 1;
-                ret_val = *self.ret_buf.clone()?[0].value;
-                ret_type_code = self.ret_buf.clone()?[0].type_code as c_int;
-                values.append(&mut vec![ret_val]);
-                tcodes.append(&mut vec![ret_type_code]);
+    /// Calls the function that created from `Builder`.
+    ///
+    /// The arguments, along with the temporaries they own, and the output are released
+    /// after the call.
+    pub fn invoke(&mut self) -> Result<RetValue> {
+        let handle = match self.func {
+            Some(ref func) => func.handle,
+            None => bail!("{}", ErrorKind::FunctionNotFound),
+        };
+        self.frame.clear();
+        for arg in self.arg_buf.iter().chain(self.ret_buf.iter()) {
+            if let Err(err) = arg.check() {
+                self.clear();
+                return Err(err);
             }
-            values.truncate(num_args);
-            tcodes.truncate(num_args);
-            check_call!(ts::TVMFuncCall(
-                self.func?.handle,
-                values.as_mut_ptr(),
-                tcodes.as_mut_ptr(),
-                num_args as c_int,
-                &mut ret_val as *mut _,
-                &mut ret_type_code as *mut _
-            ));
-        } else {
-            check_call!(ts::TVMFuncCall(
-                self.func?.handle,
-                ptr::null_mut(),
-                ptr::null_mut(),
-                0 as c_int,
-                &mut ret_val as *mut _,
-                &mut ret_type_code as *mut _
-            ));
+            self.frame.push(arg);
         }
-        let ret = TVMRetValue::new(
-            TVMValue::new(ValueKind::Return, ret_val),
-            ret_type_code.into(),
-        );
-        Ok(ret)
+        let ret = self.frame.call(handle);
+        self.clear();
+        ret
     }
 }
 
@@ -287,14 +376,14 @@ impl<'a> FnOnce<((),)> for Builder<'a> {
 /// TVM functions.
 impl<'a> From<Function> for Builder<'a> {
     fn from(func: Function) -> Self {
-        Builder::new(Some(func), None, None)
+        Builder::new(Some(func), Vec::new(), None)
     }
 }
 
 /// Converts a mutable reference of a [`Module`] to [`Builder`].
 impl<'a: 'b, 'b> From<&'b mut Module> for Builder<'a> {
     fn from(module: &mut Module) -> Self {
-        Builder::new(module.entry.take(), None, None)
+        Builder::new(module.entry.take(), Vec::new(), None)
     }
 }
 
@@ -308,24 +397,19 @@ unsafe extern "C" fn tvm_callback(
     let len = num_args as usize;
     let args_list = slice::from_raw_parts_mut(args, len);
     let type_codes_list = slice::from_raw_parts_mut(type_codes, len);
-    let mut local_args: Vec<TVMArgValue> = Vec::new();
-    // due to unsafe mem::uninitialized rustc warning about unused `value` and `tcode`.
-    let mut _value = mem::uninitialized::<ts::TVMValue>();
-    let mut _tcode = mem::uninitialized::<c_int>();
+    let mut local_args: Vec<TVMArgValue> = Vec::with_capacity(len);
     let rust_fn = mem::transmute::<*mut c_void, fn(&[TVMArgValue]) -> Result<TVMRetValue>>(fhandle);
+    // the arguments are borrowed, see `TVMArgValue::to_owned_value` for keeping them.
     for i in 0..len {
-        _value = args_list[i];
-        _tcode = type_codes_list[i];
-        if _tcode == TypeCode::kNodeHandle as c_int
-            || _tcode == TypeCode::kFuncHandle as c_int
-            || _tcode == TypeCode::kModuleHandle as c_int
-        {
-            check_call!(ts::TVMCbArgToReturn(&mut _value as *mut _, _tcode));
-        }
-        local_args.push(TVMArgValue::new(
-            TVMValue::new(ValueKind::Handle, _value),
-            _tcode.into(),
-        ));
+        let value = args_list[i];
+        let tcode = match TypeCode::try_from(type_codes_list[i]) {
+            Ok(tcode) => tcode,
+            Err(msg) => {
+                ::set_last_error(&msg);
+                return -1;
+            }
+        };
+        local_args.push(TVMArgValue::new(TVMValue::new(value), tcode));
     }
 
     let rv = match rust_fn(local_args.as_slice()) {
@@ -335,8 +419,31 @@ unsafe extern "C" fn tvm_callback(
             return -1;
         }
     };
-    let mut ret_val = *rv.value;
-    let mut ret_type_code = rv.type_code as c_int;
+    let raw = match rv.to_raw() {
+        Ok(raw) => raw,
+        Err(msg) => {
+            ::set_last_error(&msg);
+            return -1;
+        }
+    };
+    let mut ret_val = raw.value.inner;
+    let mut ret_type_code = i32::from(raw.type_code) as c_int;
+    match rv {
+        // an owned array is passed as its container so that TVM keeps its own reference
+        RetValue::NDArray(ref arr) if !arr.is_view() => {
+            ret_type_code = i32::from(TypeCode::kNDArrayContainer) as c_int;
+        }
+        RetValue::Ext(ref ext) if extension::is_registered(ext.type_code()) => {
+            let msg = ErrorKind::InvalidExtType(
+                ext.type_code(),
+                "Rust extension values cannot be returned to TVM".to_owned(),
+            );
+            ::set_last_error(&msg.into());
+            return -1;
+        }
+        _ => (),
+    }
+    // TVM copies the returned value, so the reference held by `rv` is released on drop.
     check_call!(ts::TVMCFuncSetReturn(
         ret,
         &mut ret_val as *mut _,
@@ -378,8 +485,7 @@ fn convert_to_tvm_func(f: fn(&[TVMArgValue]) -> Result<TVMRetValue>) -> Function
 ///     for arg in args.iter() {
 ///         ret += arg.to_int();
 ///     }
-///     let ret_val = TVMRetValue::from(&ret);
-///     Ok(ret_val)
+///     Ok(TVMRetValue::from(ret))
 /// }
 ///
 /// tvm::function::register(sum, "mysum".to_owned(), false).unwrap();
@@ -401,7 +507,6 @@ pub fn register(
         func.handle(),
         override_ as c_int
     ));
-    mem::forget(name);
     Ok(())
 }
 
@@ -418,8 +523,7 @@ pub fn register(
 ///         for arg in args.iter() {
 ///             ret += arg.to_float();
 ///         }
-///         let ret_val = TVMRetValue::from(&ret);
-///         Ok(ret_val)
+///         Ok(TVMRetValue::from(ret))
 ///     }
 /// }
 ///
@@ -507,7 +611,25 @@ mod tests {
         func.get_function("tvm.graph_runtime.remote_create", true)
             .args(&[10, 20])
             .arg(&"test".to_owned());
-        assert!(func.arg_buf.is_some());
-        assert_eq!(func.arg_buf.take().map(|bv| Vec::from(bv).len()), Some(3));
+        assert_eq!(func.arg_buf.len(), 3);
+        func.clear();
+        assert!(func.arg_buf.is_empty());
+    }
+
+    #[test]
+    fn call_reuse() {
+        fn mul(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+            Ok(TVMRetValue::from(args[0].to_int() * args[1].to_int()))
+        }
+        register(mul, "test.mul".to_owned(), true).unwrap();
+        let func = get_global_func("test.mul", true).unwrap();
+        let ret = func.call(&[ArgValue::from(6), ArgValue::from(7)]).unwrap();
+        assert_eq!(ret.to_int(), 42);
+        let mut builder = Builder::with_capacity(func, 2);
+        for i in 0..3i64 {
+            let ret = builder.arg(&i).arg(&i).invoke().unwrap();
+            assert_eq!(ret.to_int(), i * i);
+            assert!(builder.arg_buf.is_empty());
+        }
     }
 }
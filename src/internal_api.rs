@@ -1,6 +1,8 @@
 use std::{cell::RefCell, collections::HashMap};
 
+use ErrorKind;
 use Function;
+use Result;
 
 // access TVM internal API
 thread_local! {
@@ -28,3 +30,18 @@ pub(crate) fn get_api(name: String) -> Function {
     }
     func.expect("access to `internal_api` never panics")
 }
+
+/// Gets a global function which is registered only in some builds of TVM, such as
+/// with RPC enabled, and returns an error if it is missing.
+pub(crate) fn try_get_api(name: &str) -> Result<Function> {
+    if let Some(func) = get(name.to_owned()) {
+        return Ok(func);
+    }
+    match Function::get_function(name, true) {
+        Some(func) => {
+            set(name.to_owned(), func.clone());
+            Ok(func)
+        }
+        None => bail!(ErrorKind::MissingGlobalFunction(name.to_owned())),
+    }
+}
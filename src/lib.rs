@@ -13,8 +13,9 @@
 #![crate_name = "tvm_frontend"]
 #![recursion_limit = "1024"]
 #![allow(non_camel_case_types, unused_unsafe)]
-#![feature(try_from, try_trait, fn_traits, unboxed_closures, box_syntax)]
 
+#[cfg(feature = "bytes")]
+extern crate bytes;
 #[macro_use]
 extern crate error_chain;
 extern crate tvm_sys as ts;
@@ -22,6 +23,13 @@ extern crate tvm_sys as ts;
 extern crate lazy_static;
 extern crate ndarray as rust_ndarray;
 extern crate num_traits;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate tvm_macros;
+extern crate zip;
 
 use std::{
     ffi::{CStr, CString},
@@ -57,22 +65,35 @@ pub(crate) fn set_last_error(err: &Error) {
 #[macro_use]
 pub mod function;
 pub mod bytearray;
+pub mod container;
 pub mod context;
 pub mod errors;
+pub mod extension;
 mod internal_api;
 pub mod module;
+#[macro_use]
 pub mod ndarray;
+pub mod node;
+pub mod npy;
+pub mod rpc;
+pub mod runtime;
 pub mod ty;
 pub mod value;
 
 pub use bytearray::TVMByteArray;
-pub use context::{TVMContext, TVMDeviceType};
+pub use container::{Array, Map};
+pub use context::{devices, Device, DeviceAttributes, TVMContext, TVMDeviceType};
 pub use errors::*;
+pub use extension::{ExtType, ExtValue};
 pub use function::Function;
-pub use module::Module;
-pub use ndarray::{empty, NDArray};
+pub use module::{Module, Timing};
+pub use ndarray::{empty, save_params, NDArray};
+pub use node::NodeRef;
+pub use npy::NpzReader;
+pub use runtime::{ActivationRuntime, DebugGraphRuntime, GraphRuntime};
 pub use ty::TVMType;
-pub use value::{TVMArgValue, TVMRetValue};
+pub use tvm_macros::{register_func, FromArgs, ToArgs};
+pub use value::{ArgValue, FromArgs, RetValue, TVMArgValue, TVMRetValue, ToArgs};
 
 /// Outputs the current TVM version
 pub fn version() -> &'static str {
@@ -1,19 +1,25 @@
 //! Provides the [`Module`] type and methods for working with runtime TVM modules.
 
 use std::{
+    cmp::Ordering,
+    convert::TryFrom,
     ffi::CString,
     mem,
     os::raw::{c_char, c_int},
     path::Path,
     ptr,
+    rc::Rc,
 };
 
 use ts;
 
-use function::Function;
+use function::{self, Function};
 use internal_api;
+use ArgValue;
 use ErrorKind;
 use Result;
+use TVMArgValue;
+use TVMContext;
 
 const ENTRY_FUNC: &'static str = "__tvm_main__";
 
@@ -21,24 +27,42 @@ const ENTRY_FUNC: &'static str = "__tvm_main__";
 /// The entry function can be applied to an imported module through [`entry_func`].
 /// Also [`is_released`] shows whether the module is dropped or not.
 ///
+/// Cloning a module shares the handle, which is freed once the last clone is dropped.
+///
 /// [`entry_func`]:struct.Module.html#method.entry_func
 /// [`is_released`]:struct.Module.html#method.is_released
 #[derive(Debug, Clone)]
 pub struct Module {
     pub(crate) handle: ts::TVMModuleHandle,
-    is_released: bool,
+    // `None` when the handle is borrowed, such as a callback argument.
+    owner: Option<Rc<ModuleOwner>>,
     pub(crate) entry: Option<Function>,
 }
 
+/// Frees the module handle when dropped.
+#[derive(Debug)]
+struct ModuleOwner(ts::TVMModuleHandle);
+
+impl Drop for ModuleOwner {
+    fn drop(&mut self) {
+        check_call!(ts::TVMModFree(self.0));
+    }
+}
+
 impl Module {
     pub(crate) fn new(
         handle: ts::TVMModuleHandle,
         is_released: bool,
         entry: Option<Function>,
     ) -> Self {
+        let owner = if is_released {
+            None
+        } else {
+            Some(Rc::new(ModuleOwner(handle)))
+        };
         Self {
             handle,
-            is_released,
+            owner,
             entry,
         }
     }
@@ -63,7 +87,6 @@ impl Module {
         if fhandle.is_null() {
             bail!(ErrorKind::NullHandle(format!("{}", name.into_string()?)))
         } else {
-            mem::forget(name);
             Ok(Function::new(fhandle, false, false))
         }
     }
@@ -75,13 +98,16 @@ impl Module {
 
     /// Loads a module shared library from path.
     pub fn load(path: &Path) -> Result<Module> {
-        let path = path.to_owned();
-        let path_str = path.to_str()?.to_owned();
-        let ext = path.extension()?.to_str()?.to_owned();
+        let invalid_path = || ErrorKind::InvalidModulePath(path.display().to_string());
+        let path_str = path.to_str().ok_or_else(invalid_path)?.to_owned();
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(invalid_path)?
+            .to_owned();
         let func = internal_api::get_api("module._LoadFromFile".to_owned());
         let ret = call_packed!(func, &path_str, &ext)?;
-        mem::forget(path);
-        Ok(ret.to_module())
+        Module::try_from(ret)
     }
 
     /// Checks if a target device is enabled for a module.
@@ -98,17 +124,142 @@ impl Module {
         self.handle
     }
 
-    /// Returns true if the underlying module has been dropped and false otherwise.
+    /// Returns true if the underlying module is not released by this module,
+    /// i.e. it is borrowed, and false otherwise.
     pub fn is_released(&self) -> bool {
-        self.is_released
+        self.owner.is_none()
+    }
+
+    /// Returns an evaluator timing the function `func_name` of the module on `ctx`, in the
+    /// runtime, including the device synchronization, like `time_evaluator` in Python.
+    ///
+    /// Each of the `repeat` samples is the mean time of `number` runs, where `number` is
+    /// increased until the runs take at least `min_repeat_ms` milliseconds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// let evaluator = fadd.time_evaluator("myadd", &TVMContext::cpu(0), 10, 3, 0).unwrap();
+    /// let args = [TVMArgValue::from(&arr), TVMArgValue::from(&arr), TVMArgValue::from(&ret)];
+    /// let timing = evaluator.evaluate(&args).unwrap();
+    /// println!("mean: {}s, std: {}s", timing.mean, timing.std);
+    /// ```
+    pub fn time_evaluator(
+        &self,
+        func_name: &str,
+        ctx: &TVMContext,
+        number: usize,
+        repeat: usize,
+        min_repeat_ms: usize,
+    ) -> Result<TimeEvaluator> {
+        // renamed in later versions of TVM
+        let time_evaluator = internal_api::try_get_api("runtime.RPCTimeEvaluator")
+            .or_else(|_| internal_api::try_get_api("module._RPCTimeEvaluator"))?;
+        let ret = time_evaluator.call(&[
+            ArgValue::from(self.clone()),
+            ArgValue::from(func_name),
+            ArgValue::from(usize::from(ctx.device_type)),
+            ArgValue::from(ctx.device_id),
+            ArgValue::from(number),
+            ArgValue::from(repeat),
+            ArgValue::from(min_repeat_ms),
+            // no preprocessing such as flushing the cache, if supported
+            ArgValue::from(""),
+        ])?;
+        Ok(TimeEvaluator {
+            func: Function::try_from(ret)?,
+            repeat,
+        })
     }
 }
 
-impl Drop for Module {
-    fn drop(&mut self) {
-        if !self.is_released {
-            check_call!(ts::TVMModFree(self.handle));
-            self.is_released = true;
+/// Times a function of a module, see [`Module::time_evaluator`].
+///
+/// [`Module::time_evaluator`]:struct.Module.html#method.time_evaluator
+#[derive(Debug, Clone)]
+pub struct TimeEvaluator {
+    func: Function,
+    repeat: usize,
+}
+
+impl TimeEvaluator {
+    /// Runs the function with `args` and returns its timing.
+    pub fn evaluate(&self, args: &[TVMArgValue]) -> Result<Timing> {
+        let ret = function::Builder::from(self.func.clone())
+            .args(args)
+            .invoke()?;
+        let blob = Vec::<u8>::try_from(ret)?;
+        let sample_size = mem::size_of::<f64>();
+        if blob.len() != self.repeat * sample_size {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "expected {} timing samples, but found {} bytes",
+                self.repeat,
+                blob.len()
+            )));
+        }
+        let samples = blob
+            .chunks(sample_size)
+            .map(|sample| {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(sample);
+                f64::from_ne_bytes(bytes)
+            })
+            .collect();
+        Ok(Timing::from_samples(samples))
+    }
+}
+
+/// Timing of a function in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub mean: f64,
+    pub median: f64,
+    /// Population standard deviation, like `numpy.std`.
+    pub std: f64,
+    /// Mean time of the runs of each repeat.
+    pub samples: Vec<f64>,
+}
+
+impl Timing {
+    /// Computes the statistics of the samples.
+    pub fn from_samples(samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Timing {
+                mean: 0.0,
+                median: 0.0,
+                std: 0.0,
+                samples,
+            };
+        }
+        let num_samples = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / num_samples;
+        let var = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / num_samples;
+        let mut sorted = samples.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        // both indices are the middle one for an odd length
+        let len = sorted.len();
+        let median = (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0;
+        Timing {
+            mean,
+            median,
+            std: var.sqrt(),
+            samples,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing() {
+        let timing = Timing::from_samples(vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(timing.mean, 2.5);
+        assert_eq!(timing.median, 2.5);
+        assert!((timing.std - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(timing.samples, vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(Timing::from_samples(vec![3.0, 1.0, 2.0]).median, 2.0);
+        assert_eq!(Timing::from_samples(Vec::new()).mean, 0.0);
+    }
+}
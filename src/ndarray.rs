@@ -4,6 +4,7 @@
 //! One can create an empty NDArray given the shape, context and dtype using [`empty`].
 //! To create an NDArray from a mutable buffer in cpu use [`copy_from_buffer`].
 //! To copy an NDArray to different context use [`copy_to_ctx`].
+//! To persist an NDArray in TVM's binary format use [`save`] and [`load`].
 //!
 //! Given a [`Rust's dynamic ndarray`], one can convert it to TVM NDArray as follows:
 //!
@@ -22,8 +23,18 @@
 //! [`Rust's dynamic ndarray`]:https://docs.rs/ndarray/0.12.1/ndarray/
 //! [`copy_from_buffer`]:struct.NDArray.html#method.copy_from_buffer
 //! [`copy_to_ctx`]:struct.NDArray.html#method.copy_to_ctx
-
-use std::{convert::TryFrom, mem, os::raw::c_int, ptr, slice};
+//! [`save`]:struct.NDArray.html#method.save
+//! [`load`]:struct.NDArray.html#method.load
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::{self, Debug, Display, Formatter},
+    io::{Read, Write},
+    mem,
+    os::raw::c_int,
+    ptr, slice,
+};
 
 use num_traits::Num;
 use rust_ndarray::{Array, ArrayD};
@@ -37,10 +48,14 @@ use TVMByteArray;
 use TVMContext;
 use TVMType;
 
+/// Magic number of a serialized `DLTensor`, see `kTVMNDArrayMagic` in TVM.
+const NDARRAY_MAGIC: u64 = 0xDD5E40F096B4A13F;
+/// Magic number of serialized named arrays, see `kTVMNDArrayListMagic` in TVM.
+const NDARRAY_LIST_MAGIC: u64 = 0xF7E58D4F05049CB7;
+
 /// See the [`module-level documentation`](../ndarray/index.html) for more details.
 ///
 /// Wrapper around TVM array handle.
-#[derive(Debug)]
 pub struct NDArray {
     pub(crate) handle: ts::TVMArrayHandle,
     is_view: bool,
@@ -79,9 +94,16 @@ impl NDArray {
             .map(|v| v.into_iter().fold(1, |acc, &mut e| acc * e))
     }
 
-    /// Returns the context which the NDArray was defined.
-    pub fn ctx(&self) -> TVMContext {
-        unsafe { (*self.handle).ctx.into() }
+    /// Returns the total number of bytes of the NDArray's data.
+    pub fn nbytes(&self) -> Option<usize> {
+        let itemsize = self.dtype().itemsize();
+        self.size().map(|sz| sz * itemsize)
+    }
+
+    /// Returns the context which the NDArray was defined. Returns an error if TVM reports
+    /// an unknown device.
+    pub fn ctx(&self) -> Result<TVMContext> {
+        TVMContext::try_from(unsafe { (*self.handle).ctx })
     }
 
     /// Returns the type of the entries of the NDArray.
@@ -95,10 +117,14 @@ impl NDArray {
     }
 
     /// Returns the strides of the underlying NDArray.
+    /// Returns `None` for compact row-major NDArrays.
     pub fn strides(&self) -> Option<&[usize]> {
         unsafe {
-            let sz = self.ndim() * mem::size_of::<usize>();
-            let slc = slice::from_raw_parts((*self.handle).strides as *const usize, sz);
+            let strides = (*self.handle).strides;
+            if strides.is_null() {
+                return None;
+            }
+            let slc = slice::from_raw_parts(strides as *const usize, self.ndim());
             Some(slc)
         }
     }
@@ -108,8 +134,8 @@ impl NDArray {
         Ok(match self.strides() {
             None => true,
             Some(strides) => {
-                // MissingShapeError in case shape is not determined
-                self.shape()?
+                self.shape()
+                    .ok_or(ErrorKind::MissingShapeError)?
                     .iter()
                     .zip(strides)
                     .rfold(
@@ -147,10 +173,14 @@ impl NDArray {
         if self.shape().is_none() {
             bail!("{}", ErrorKind::EmptyArray);
         }
-        let earr = empty(self.shape()?, TVMContext::cpu(0), self.dtype());
+        let earr = empty(
+            self.shape().ok_or(ErrorKind::MissingShapeError)?,
+            TVMContext::cpu(0),
+            self.dtype(),
+        );
         let target = self.copy_to_ndarray(earr)?;
         let arr = unsafe { *(target.handle) };
-        let sz = self.size()? as usize;
+        let sz = self.size().ok_or(ErrorKind::MissingShapeError)?;
         let mut v: Vec<T> = Vec::with_capacity(sz * mem::size_of::<T>());
         unsafe {
             v.as_mut_ptr()
@@ -160,10 +190,45 @@ impl NDArray {
         Ok(v)
     }
 
-    /// Converts the NDArray to [`TVMByteArray`].
-    pub fn to_bytearray(&self) -> Result<TVMByteArray> {
-        let v = self.to_vec::<u8>()?;
-        Ok(TVMByteArray::from(&v))
+    /// Copies the raw data of the NDArray to a byte buffer in cpu.
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>> {
+        let nbytes = match self.nbytes() {
+            Some(nbytes) => nbytes,
+            None => bail!("{}", ErrorKind::EmptyArray),
+        };
+        let mut buf = vec![0u8; nbytes];
+        check_call!(ts::TVMArrayCopyToBytes(
+            self.handle,
+            buf.as_mut_ptr() as *mut _,
+            nbytes
+        ));
+        Ok(buf)
+    }
+
+    /// Copies the NDArray to cpu and converts each entry to `f64`.
+    /// Vector types are flattened lane by lane.
+    pub(crate) fn to_f64_vec(&self) -> Result<Vec<f64>> {
+        let dtype = self.dtype();
+        let decode = lane_decoder(&dtype)?;
+        let bytes = self.to_bytes()?;
+        Ok(bytes
+            .chunks(lane_bytes(&dtype))
+            .map(|lane| decode(lane).to_f64())
+            .collect())
+    }
+
+    /// Fills the NDArray with raw bytes which must match its size in bytes.
+    pub(crate) fn copy_from_bytes(&mut self, data: &[u8]) {
+        check_call!(ts::TVMArrayCopyFromBytes(
+            self.handle,
+            data.as_ptr() as *mut _,
+            data.len()
+        ));
+    }
+
+    /// Converts the NDArray to an owned [`TVMByteArray`] of its raw data.
+    pub fn to_bytearray(&self) -> Result<TVMByteArray<'static>> {
+        Ok(TVMByteArray::from(self.to_bytes()?))
     }
 
     /// Creates an NDArray from a mutable buffer of types i32, u32 or f32 in cpu.
@@ -209,7 +274,11 @@ impl NDArray {
 
     /// Copies the NDArray to a target context.
     pub fn copy_to_ctx(&self, target: &TVMContext) -> Result<NDArray> {
-        let tmp = empty(self.shape()?, target.clone(), self.dtype());
+        let tmp = empty(
+            self.shape().ok_or(ErrorKind::MissingShapeError)?,
+            target.clone(),
+            self.dtype(),
+        );
         let copy = self.copy_to_ndarray(tmp)?;
         Ok(copy)
     }
@@ -222,10 +291,438 @@ impl NDArray {
     ) -> Result<Self> {
         let mut shape = rnd.shape().to_vec();
         let mut nd = empty(&mut shape, ctx, dtype);
-        let mut buf = Array::from_iter(rnd.into_iter().map(|&v| v as T));
-        nd.copy_from_buffer(buf.as_slice_mut()?);
+        let mut buf = rnd.iter().cloned().collect::<Vec<T>>();
+        nd.copy_from_buffer(&mut buf);
+        Ok(nd)
+    }
+
+    /// Serializes the NDArray to a writer in TVM's `DLTensor` binary format
+    /// which is the same format used by `SaveDLTensor` in the C++ runtime.
+    /// The data is always stored as if it lived in cpu.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// let mut buf = Vec::new();
+    /// ndarray.save(&mut buf).unwrap();
+    /// let loaded = NDArray::load(&mut &buf[..]).unwrap();
+    /// assert_eq!(loaded.to_vec::<f32>().unwrap(), ndarray.to_vec::<f32>().unwrap());
+    /// ```
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let shape = match self.shape() {
+            Some(shape) => shape.to_vec(),
+            None => bail!("{}", ErrorKind::EmptyArray),
+        };
+        let dtype = self.dtype();
+        let data = self.to_bytes()?;
+        writer.write_all(&NDARRAY_MAGIC.to_le_bytes())?;
+        // reserved
+        writer.write_all(&0u64.to_le_bytes())?;
+        writer.write_all(&(ts::DLDeviceType_kDLCPU as i32).to_le_bytes())?;
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&(shape.len() as i32).to_le_bytes())?;
+        writer.write_all(&[dtype.code, dtype.bits])?;
+        writer.write_all(&dtype.lanes.to_le_bytes())?;
+        for dim in shape {
+            writer.write_all(&(dim as i64).to_le_bytes())?;
+        }
+        writer.write_all(&(data.len() as i64).to_le_bytes())?;
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Deserializes an NDArray in cpu from a reader containing data written
+    /// by [`save`] or by `SaveDLTensor` in the C++ runtime.
+    ///
+    /// [`save`]:struct.NDArray.html#method.save
+    pub fn load<R: Read>(reader: &mut R) -> Result<NDArray> {
+        if read_u64(reader)? != NDARRAY_MAGIC {
+            bail!(ErrorKind::InvalidFormat(
+                "invalid DLTensor magic number".to_owned()
+            ));
+        }
+        // reserved
+        read_u64(reader)?;
+        let device_type = read_i32(reader)?;
+        let _device_id = read_i32(reader)?;
+        if device_type != ts::DLDeviceType_kDLCPU as i32 {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "DLTensor must be saved in cpu, but found device type {}",
+                device_type
+            )));
+        }
+        let ndim = read_i32(reader)?;
+        if ndim < 0 {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "invalid number of dimensions {}",
+                ndim
+            )));
+        }
+        let mut dtype_buf = [0u8; 4];
+        reader.read_exact(&mut dtype_buf)?;
+        let dtype = TVMType::new(
+            dtype_buf[0],
+            dtype_buf[1],
+            u16::from_le_bytes([dtype_buf[2], dtype_buf[3]]),
+        );
+        let mut shape = Vec::with_capacity(ndim as usize);
+        for _ in 0..ndim {
+            let dim = read_i64(reader)?;
+            if dim < 0 {
+                bail!(ErrorKind::InvalidFormat(format!("invalid dimension {}", dim)));
+            }
+            shape.push(dim as usize);
+        }
+        let nbytes = shape.iter().product::<usize>() * dtype.itemsize();
+        let data_size = read_i64(reader)?;
+        if data_size != nbytes as i64 {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "expected {} bytes of data, but found {}",
+                nbytes, data_size
+            )));
+        }
+        let mut data = vec![0u8; nbytes];
+        reader.read_exact(&mut data)?;
+        let mut nd = empty(&mut shape, TVMContext::cpu(0), dtype);
+        nd.copy_from_bytes(&data);
         Ok(nd)
     }
+
+    /// Returns `true` if both NDArrays have the same shape and dtype and all their
+    /// entries satisfy `|self - other| <= atol + rtol * |other|` (same as `numpy.allclose`).
+    /// NDArrays in other contexts are copied to cpu for the comparison.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// let a = NDArray::from_rust_ndarray(&arr, TVMContext::cpu(0), TVMType::from("float")).unwrap();
+    /// let b = a.copy_to_ctx(&TVMContext::gpu(0)).unwrap();
+    /// assert!(a.allclose(&b, 1e-5, 1e-8).unwrap());
+    /// ```
+    pub fn allclose(&self, other: &NDArray, rtol: f64, atol: f64) -> Result<bool> {
+        Ok(self.close_report(other, rtol, atol)?.is_none())
+    }
+
+    /// Compares two NDArrays like [`allclose`] and returns `None` if they are close,
+    /// otherwise a report of their shape or dtype mismatch, or their worst
+    /// mismatching entries and indices. Used by [`assert_ndarray_close`].
+    ///
+    /// [`allclose`]:struct.NDArray.html#method.allclose
+    /// [`assert_ndarray_close`]:../macro.assert_ndarray_close.html
+    pub fn close_report(&self, other: &NDArray, rtol: f64, atol: f64) -> Result<Option<String>> {
+        let (shape, other_shape) = match (self.shape(), other.shape()) {
+            (Some(shape), Some(other_shape)) => (shape.to_vec(), other_shape.to_vec()),
+            _ => bail!("{}", ErrorKind::EmptyArray),
+        };
+        if shape != other_shape {
+            return Ok(Some(format!(
+                "shape mismatch: {:?} != {:?}",
+                shape, other_shape
+            )));
+        }
+        if self.dtype() != other.dtype() {
+            return Ok(Some(format!(
+                "dtype mismatch: {:?} != {:?}",
+                self.dtype(),
+                other.dtype()
+            )));
+        }
+        let lanes = self.dtype().lanes as usize;
+        let left = self.to_f64_vec()?;
+        let right = other.to_f64_vec()?;
+        let mut mismatches = left
+            .iter()
+            .zip(right.iter())
+            .enumerate()
+            .filter(|&(_, (&l, &r))| !((l - r).abs() <= atol + rtol * r.abs()))
+            .map(|(i, (&l, &r))| (i, l, r, (l - r).abs()))
+            .collect::<Vec<_>>();
+        if mismatches.is_empty() {
+            return Ok(None);
+        }
+        // NaN differences are reported first
+        mismatches.sort_by(|a, b| {
+            b.3.partial_cmp(&a.3)
+                .unwrap_or_else(|| b.3.is_nan().cmp(&a.3.is_nan()))
+        });
+        let mut report = format!(
+            "{} of {} entries are not close (rtol: {}, atol: {}), worst mismatches:",
+            mismatches.len(),
+            left.len(),
+            rtol,
+            atol
+        );
+        for &(i, l, r, diff) in mismatches.iter().take(MAX_REPORTED_MISMATCHES) {
+            let mut index = unravel_index(i / lanes, &shape);
+            if lanes > 1 {
+                index.push(i % lanes);
+            }
+            report.push_str(&format!(
+                "\n  at {:?}: {} != {} (diff: {})",
+                index, l, r, diff
+            ));
+        }
+        Ok(Some(report))
+    }
+}
+
+impl Debug for NDArray {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("NDArray")
+            .field("handle", &self.handle)
+            .field("shape", &self.shape())
+            .field("dtype", &self.dtype())
+            .field("ctx", &self.ctx().ok())
+            .field("strides", &self.strides())
+            .field("byte_offset", &self.byte_offset())
+            .field("is_view", &self.is_view)
+            .finish()
+    }
+}
+
+/// Prints the shape, dtype and context followed by the entries in NumPy style.
+/// NDArrays with more than 1000 entries are summarized by their first and
+/// last three entries along each axis. NDArrays in other contexts are copied to cpu.
+impl Display for NDArray {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let ctx = match self.ctx() {
+            Ok(ctx) => ctx.to_string(),
+            Err(_) => "unknown".to_owned(),
+        };
+        let shape = match self.shape() {
+            Some(shape) => shape.to_vec(),
+            None => return write!(f, "NDArray(empty, dtype={}, ctx={})", self.dtype(), ctx),
+        };
+        writeln!(
+            f,
+            "NDArray(shape={:?}, dtype={}, ctx={})",
+            shape,
+            self.dtype(),
+            ctx
+        )?;
+        let dtype = self.dtype();
+        let lanes = dtype.lanes as usize;
+        let lane_bytes = lane_bytes(&dtype);
+        let (decode, bytes) = match (lane_decoder(&dtype), self.to_bytes()) {
+            (Ok(decode), Ok(bytes)) => (decode, bytes),
+            _ => return write!(f, "[...]"),
+        };
+        // only the printed entries are decoded
+        let fmt_entry = |i: usize| {
+            let entries: Vec<_> = (i * lanes..(i + 1) * lanes)
+                .map(|lane| decode(&bytes[lane * lane_bytes..(lane + 1) * lane_bytes]).to_string())
+                .collect();
+            if lanes > 1 {
+                format!("({})", entries.join(", "))
+            } else {
+                entries[0].clone()
+            }
+        };
+        let summarize = shape.iter().product::<usize>() > PRINT_THRESHOLD;
+        let entries = visible_indices(&shape, summarize)
+            .into_iter()
+            .map(|i| (i, fmt_entry(i)))
+            .collect::<HashMap<_, _>>();
+        let width = entries.values().map(|e| e.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        fmt_axis(&mut out, &shape, 0, 0, summarize, &|i| {
+            format!("{:>width$}", entries[&i], width = width)
+        });
+        write!(f, "{}", out)
+    }
+}
+
+/// Lane of an NDArray entry decoded from its native type, since 64-bit integers do not
+/// fit in `f64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lane {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl Lane {
+    fn to_f64(self) -> f64 {
+        match self {
+            Lane::Int(v) => v as f64,
+            Lane::UInt(v) => v as f64,
+            Lane::Float(v) => v,
+        }
+    }
+}
+
+impl Display for Lane {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Lane::Int(v) => write!(f, "{}", v),
+            Lane::UInt(v) => write!(f, "{}", v),
+            Lane::Float(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+// Returns the number of bytes of a lane of `dtype`.
+fn lane_bytes(dtype: &TVMType) -> usize {
+    (dtype.bits as usize + 7) / 8
+}
+
+// Returns the decoder of a lane of `dtype` from its bytes, which are in host byte order.
+fn lane_decoder(dtype: &TVMType) -> Result<fn(&[u8]) -> Lane> {
+    macro_rules! decoder {
+        ($ty:ty, $n:expr, $lane:ident, $as:ty) => {
+            |bytes: &[u8]| {
+                let mut buf = [0u8; $n];
+                buf.copy_from_slice(bytes);
+                Lane::$lane(<$ty>::from_ne_bytes(buf) as $as)
+            }
+        };
+    }
+    Ok(match (dtype.code, dtype.bits) {
+        (0, 8) => decoder!(i8, 1, Int, i64),
+        (0, 16) => decoder!(i16, 2, Int, i64),
+        (0, 32) => decoder!(i32, 4, Int, i64),
+        (0, 64) => decoder!(i64, 8, Int, i64),
+        (1, 1) | (1, 8) => decoder!(u8, 1, UInt, u64),
+        (1, 16) => decoder!(u16, 2, UInt, u64),
+        (1, 32) => decoder!(u32, 4, UInt, u64),
+        (1, 64) => decoder!(u64, 8, UInt, u64),
+        (2, 16) => |bytes: &[u8]| Lane::Float(f16_to_f64(u16::from_ne_bytes([bytes[0], bytes[1]]))),
+        (2, 32) => decoder!(f32, 4, Float, f64),
+        (2, 64) => decoder!(f64, 8, Float, f64),
+        _ => bail!(ErrorKind::TypeMismatch(
+            "numeric type".to_owned(),
+            format!("{:?}", dtype)
+        )),
+    })
+}
+
+// NDArrays with more entries than this are summarized when printed
+const PRINT_THRESHOLD: usize = 1000;
+
+// number of entries printed at each side of a summarized axis
+const PRINT_EDGE_ITEMS: usize = 3;
+
+// Returns the indices along an axis of length `dim` which are printed.
+fn visible_axis(dim: usize, summarize: bool) -> Vec<Option<usize>> {
+    if summarize && dim > 2 * PRINT_EDGE_ITEMS {
+        (0..PRINT_EDGE_ITEMS)
+            .map(Some)
+            .chain(Some(None))
+            .chain((dim - PRINT_EDGE_ITEMS..dim).map(Some))
+            .collect()
+    } else {
+        (0..dim).map(Some).collect()
+    }
+}
+
+// Returns the flat indices of all the printed entries.
+fn visible_indices(shape: &[usize], summarize: bool) -> Vec<usize> {
+    shape.iter().fold(vec![0], |acc, &dim| {
+        let axis = visible_axis(dim, summarize);
+        acc.iter()
+            .flat_map(|&base| axis.iter().filter_map(move |&i| i.map(|i| base * dim + i)))
+            .collect()
+    })
+}
+
+fn fmt_axis(
+    out: &mut String,
+    shape: &[usize],
+    depth: usize,
+    base: usize,
+    summarize: bool,
+    entry: &dyn Fn(usize) -> String,
+) {
+    if depth == shape.len() {
+        out.push_str(&entry(base));
+        return;
+    }
+    let dim = shape[depth];
+    let separator = if depth + 1 == shape.len() {
+        ", ".to_owned()
+    } else {
+        // rows are separated by a new line and higher dimensions by an empty line
+        let new_lines = "\n".repeat(shape.len() - depth - 1);
+        format!(",{}{}", new_lines, " ".repeat(depth + 1))
+    };
+    out.push('[');
+    for (n, i) in visible_axis(dim, summarize).into_iter().enumerate() {
+        if n > 0 {
+            out.push_str(&separator);
+        }
+        match i {
+            Some(i) => fmt_axis(out, shape, depth + 1, base * dim + i, summarize, entry),
+            None => out.push_str("..."),
+        }
+    }
+    out.push(']');
+}
+
+// maximum number of entries listed in `NDArray::close_report`
+const MAX_REPORTED_MISMATCHES: usize = 5;
+
+fn unravel_index(mut flat: usize, shape: &[usize]) -> Vec<usize> {
+    let mut index = vec![0; shape.len()];
+    for (i, &dim) in shape.iter().enumerate().rev() {
+        if dim > 0 {
+            index[i] = flat % dim;
+            flat /= dim;
+        }
+    }
+    index
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half >> 15 == 1 { -1f64 } else { 1f64 };
+    let exp = ((half >> 10) & 0x1f) as i32;
+    let frac = (half & 0x3ff) as f64;
+    sign * match exp {
+        0 => frac * 2f64.powi(-24),
+        0x1f if frac == 0f64 => ::std::f64::INFINITY,
+        0x1f => ::std::f64::NAN,
+        _ => (1f64 + frac / 1024f64) * 2f64.powi(exp - 15),
+    }
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> Result<i64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(i64::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Serializes named arrays in the format of the parameters saved by TVM, such as
+/// `deploy_param.params`, which can be loaded by `load_params` of a graph runtime.
+pub fn save_params<S, W>(params: &[(S, &NDArray)], writer: &mut W) -> Result<()>
+where
+    S: AsRef<str>,
+    W: Write,
+{
+    writer.write_all(&NDARRAY_LIST_MAGIC.to_le_bytes())?;
+    // reserved
+    writer.write_all(&0u64.to_le_bytes())?;
+    writer.write_all(&(params.len() as u64).to_le_bytes())?;
+    for (name, _) in params {
+        let name = name.as_ref();
+        writer.write_all(&(name.len() as u64).to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+    }
+    writer.write_all(&(params.len() as u64).to_le_bytes())?;
+    for &(_, array) in params {
+        array.save(writer)?;
+    }
+    Ok(())
 }
 
 /// Allocates and creates an empty NDArray given the shape, context and dtype.
@@ -237,7 +734,7 @@ pub fn empty(shape: &mut [usize], ctx: TVMContext, dtype: TVMType) -> NDArray {
         dtype.inner.code as c_int,
         dtype.inner.bits as c_int,
         dtype.inner.lanes as c_int,
-        ctx.device_type.0 as c_int,
+        usize::from(ctx.device_type) as c_int,
         ctx.device_id as c_int,
         &mut handle as *mut _,
     ));
@@ -253,7 +750,8 @@ macro_rules! impl_from_ndarray_rustndarray {
                     bail!("{}", ErrorKind::EmptyArray);
                 }
                 assert_eq!(nd.dtype(), TVMType::from($type_name), "Type mismatch");
-                Ok(Array::from_shape_vec(&*nd.shape()?, nd.to_vec::<$type>()?)?)
+                let shape = nd.shape().ok_or(ErrorKind::MissingShapeError)?.to_vec();
+                Ok(Array::from_shape_vec(shape, nd.to_vec::<$type>()?)?)
             }
         }
 
@@ -264,7 +762,8 @@ macro_rules! impl_from_ndarray_rustndarray {
                     bail!("{}", ErrorKind::EmptyArray);
                 }
                 assert_eq!(nd.dtype(), TVMType::from($type_name), "Type mismatch");
-                Ok(Array::from_shape_vec(&*nd.shape()?, nd.to_vec::<$type>()?)?)
+                let shape = nd.shape().ok_or(ErrorKind::MissingShapeError)?.to_vec();
+                Ok(Array::from_shape_vec(shape, nd.to_vec::<$type>()?)?)
             }
         }
     };
@@ -297,6 +796,38 @@ macro_rules! impl_num32 {
 
 impl_num32!(i32, u32, f32);
 
+/// Asserts that two [`NDArray`]s are close with respect to the relative and absolute
+/// tolerances `rtol` (default `1e-5`) and `atol` (default `1e-8`). On failure it
+/// panics with the shape or dtype mismatch, or with the worst mismatching entries
+/// and their indices.
+///
+/// ## Example
+///
+/// ```
+/// assert_ndarray_close!(output, expected);
+/// assert_ndarray_close!(output, expected, 1e-3, 1e-5);
+/// ```
+///
+/// [`NDArray`]:ndarray/struct.NDArray.html
+#[macro_export]
+macro_rules! assert_ndarray_close {
+    ($left:expr, $right:expr) => {
+        assert_ndarray_close!($left, $right, 1e-5, 1e-8)
+    };
+    ($left:expr, $right:expr, $rtol:expr, $atol:expr) => {{
+        match $left.close_report(&$right, $rtol, $atol) {
+            Ok(None) => (),
+            Ok(Some(report)) => panic!(
+                "assertion failed: `{}` is not close to `{}`\n{}",
+                stringify!($left),
+                stringify!($right),
+                report
+            ),
+            Err(err) => panic!("cannot compare NDArrays: {}", err),
+        }
+    }};
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,7 +840,7 @@ mod tests {
         assert_eq!(ndarray.shape().unwrap(), shape);
         assert_eq!(
             ndarray.size().unwrap(),
-            shape.to_vec().into_iter().product()
+            shape.to_vec().into_iter().product::<usize>()
         );
         assert_eq!(ndarray.ndim(), 3);
         assert!(ndarray.strides().is_none());
@@ -359,4 +890,106 @@ mod tests {
         let rnd: ArrayD<f32> = ArrayD::try_from(&nd).unwrap();
         assert!(rnd.all_close(&a, 1e-8f32));
     }
+
+    #[test]
+    fn allclose() {
+        let shape = &mut [2, 2];
+        let mut data = vec![1f32, 2., 3., 4.];
+        let mut a = empty(shape, TVMContext::cpu(0), TVMType::from("float"));
+        a.copy_from_buffer(&mut data);
+        let mut b = empty(shape, TVMContext::cpu(0), TVMType::from("float"));
+        b.copy_from_buffer(&mut vec![1f32, 2., 3.5, 4.]);
+        assert!(a.allclose(&a, 1e-5, 1e-8).unwrap());
+        assert!(!a.allclose(&b, 1e-5, 1e-8).unwrap());
+        assert!(a.allclose(&b, 0., 0.5).unwrap());
+        let report = a.close_report(&b, 1e-5, 1e-8).unwrap().unwrap();
+        assert!(report.contains("1 of 4"));
+        assert!(report.contains("[1, 0]"));
+        let c = empty(&mut [4], TVMContext::cpu(0), TVMType::from("float"));
+        assert!(a.close_report(&c, 1e-5, 1e-8).unwrap().unwrap().contains("shape"));
+        let d = empty(shape, TVMContext::cpu(0), TVMType::from("int"));
+        assert!(a.close_report(&d, 1e-5, 1e-8).unwrap().unwrap().contains("dtype"));
+        assert_ndarray_close!(a, a);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not close")]
+    fn assert_close() {
+        let shape = &mut [2];
+        let mut a = empty(shape, TVMContext::cpu(0), TVMType::from("int"));
+        a.copy_from_buffer(&mut vec![1i32, 2]);
+        let mut b = empty(shape, TVMContext::cpu(0), TVMType::from("int"));
+        b.copy_from_buffer(&mut vec![1i32, 3]);
+        assert_ndarray_close!(a, b);
+    }
+
+    #[test]
+    fn display() {
+        let shape = &mut [2, 2];
+        let mut a = empty(shape, TVMContext::cpu(0), TVMType::from("int"));
+        a.copy_from_buffer(&mut vec![1i32, 2, 30, 4]);
+        assert_eq!(
+            a.to_string(),
+            "NDArray(shape=[2, 2], dtype=int, ctx=cpu(0))\n[[ 1,  2],\n [30,  4]]"
+        );
+        let mut data = (0..2000).map(|v| v as f32).collect::<Vec<_>>();
+        let mut b = empty(&mut [2000], TVMContext::cpu(0), TVMType::from("float"));
+        b.copy_from_buffer(&mut data);
+        assert!(b.to_string().ends_with(
+            "[   0.0,    1.0,    2.0, ..., 1997.0, 1998.0, 1999.0]"
+        ));
+        let mut c = empty(&mut [2], TVMContext::cpu(0), TVMType::new(0, 64, 1));
+        let bytes = [(1i64 << 53) + 1, -1]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        c.copy_from_bytes(&bytes);
+        assert!(c
+            .to_string()
+            .ends_with(&format!("[9007199254740993, {:>16}]", -1)));
+        let debug = format!("{:?}", a);
+        assert!(debug.contains("strides: None"));
+        assert!(debug.contains("is_view: false"));
+    }
+
+    #[test]
+    fn half() {
+        assert_eq!(f16_to_f64(0x3c00), 1.);
+        assert_eq!(f16_to_f64(0xc000), -2.);
+        assert_eq!(f16_to_f64(0x0001), 2f64.powi(-24));
+        assert!(f16_to_f64(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn save_load() {
+        let shape = &mut [2, 2];
+        let mut data = vec![1f32, 2., 3., 4.];
+        let mut ndarray = empty(shape, TVMContext::cpu(0), TVMType::from("float"));
+        ndarray.copy_from_buffer(&mut data);
+        let mut buf = Vec::new();
+        ndarray.save(&mut buf).unwrap();
+        assert_eq!(&buf[..8], &NDARRAY_MAGIC.to_le_bytes());
+        let loaded = NDArray::load(&mut &buf[..]).unwrap();
+        assert_eq!(loaded.shape().unwrap(), shape);
+        assert_eq!(loaded.dtype(), TVMType::from("float"));
+        assert_eq!(loaded.to_vec::<f32>().unwrap(), data);
+        buf[0] = 0;
+        assert!(NDArray::load(&mut &buf[..]).is_err());
+    }
+
+    #[test]
+    fn save_params() {
+        let shape = &mut [2];
+        let mut data = vec![1f32, 2.];
+        let mut ndarray = empty(shape, TVMContext::cpu(0), TVMType::from("float"));
+        ndarray.copy_from_buffer(&mut data);
+        let mut buf = Vec::new();
+        super::save_params(&[("w", &ndarray)], &mut buf).unwrap();
+        assert_eq!(&buf[..8], &NDARRAY_LIST_MAGIC.to_le_bytes());
+        // magic, reserved, number of names, name length, name and number of arrays
+        assert_eq!(&buf[8 * 4..8 * 4 + 1], b"w");
+        assert_eq!(&buf[8 * 4 + 1..8 * 5 + 1], &1u64.to_le_bytes());
+        let loaded = NDArray::load(&mut &buf[8 * 5 + 1..]).unwrap();
+        assert_eq!(loaded.to_vec::<f32>().unwrap(), data);
+    }
 }
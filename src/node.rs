@@ -0,0 +1,200 @@
+//! Provides the [`NodeRef`] type referring to TVM nodes, such as the values returned by
+//! compiler-side functions, and methods for inspecting them.
+//!
+//! Attributes are retrieved through the `_NodeGetAttr` and `_NodeListAttrNames` global
+//! functions and nodes are saved to and loaded from JSON through `_save_json` and `_load_json`.
+//!
+//! *Note:* node handles can only be released by the full `libtvm`, so they are freed
+//! when the `compiler` feature is enabled and leaked otherwise.
+//!
+//! # Example
+//!
+//! ```
+//! let node = NodeRef::from_json(&json).unwrap();
+//! println!("{}: {:?}", node.type_key().unwrap(), node.attr_names().unwrap());
+//! ```
+
+use std::{convert::TryFrom, os::raw::c_void, rc::Rc};
+#[cfg(feature = "compiler")]
+use std::{collections::HashMap, ffi::CString, sync::Mutex};
+
+use serde_json::{self, Value};
+#[cfg(feature = "compiler")]
+use ts;
+
+use internal_api;
+use ArgValue;
+use ErrorKind;
+use Function;
+use Result;
+use RetValue;
+
+/// Wrapper around a TVM node handle.
+///
+/// Cloning a node shares the handle, which is released once the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct NodeRef {
+    pub(crate) handle: *mut c_void,
+    // `None` when the handle is borrowed, such as a callback argument.
+    owner: Option<Rc<NodeOwner>>,
+}
+
+/// Releases the node handle when dropped.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "compiler"), allow(dead_code))]
+struct NodeOwner(*mut c_void);
+
+impl Drop for NodeOwner {
+    fn drop(&mut self) {
+        #[cfg(feature = "compiler")]
+        check_call!(ts::TVMNodeFree(self.0));
+    }
+}
+
+impl NodeRef {
+    pub(crate) fn new(handle: *mut c_void, is_released: bool) -> Self {
+        let owner = if is_released {
+            None
+        } else {
+            Some(Rc::new(NodeOwner(handle)))
+        };
+        NodeRef { handle, owner }
+    }
+
+    /// Returns the underlying node handle.
+    pub fn handle(&self) -> *mut c_void {
+        self.handle
+    }
+
+    /// Returns true if the underlying node is not released by this node,
+    /// i.e. it is borrowed, and false otherwise.
+    pub fn is_released(&self) -> bool {
+        self.owner.is_none()
+    }
+
+    /// Returns the type key of the node such as `"Variable"`.
+    ///
+    /// TVM only maps type keys to indices, so the key of each type is read once from
+    /// the JSON of a node and cached by type index with the `compiler` feature. Without
+    /// it the node is saved as JSON on every call.
+    pub fn type_key(&self) -> Result<String> {
+        #[cfg(feature = "compiler")]
+        {
+            let index = self.type_index()?;
+            if let Some(key) = TYPE_KEYS.lock().unwrap().get(&index) {
+                return Ok(key.clone());
+            }
+            let key = root_type_key(&self.to_json()?)?;
+            TYPE_KEYS.lock().unwrap().insert(index, key.clone());
+            Ok(key)
+        }
+        #[cfg(not(feature = "compiler"))]
+        root_type_key(&self.to_json()?)
+    }
+
+    /// Returns the index of the type of the node.
+    #[cfg(feature = "compiler")]
+    pub fn type_index(&self) -> Result<i32> {
+        let mut index = 0;
+        if unsafe { ts::TVMNodeGetTypeIndex(self.handle, &mut index as *mut _) } != 0 {
+            bail!(ErrorKind::TVMError(::get_last_error().to_owned()));
+        }
+        Ok(index)
+    }
+
+    /// Returns true if the type of the node is `type_key`.
+    #[cfg(feature = "compiler")]
+    pub fn is_instance(&self, type_key: &str) -> Result<bool> {
+        let type_key = CString::new(type_key)?;
+        let mut index = 0;
+        if unsafe { ts::TVMNodeTypeKey2Index(type_key.as_ptr(), &mut index as *mut _) } != 0 {
+            bail!(ErrorKind::TVMError(::get_last_error().to_owned()));
+        }
+        Ok(self.type_index()? == index)
+    }
+
+    /// Returns the names of the attributes of the node.
+    pub fn attr_names(&self) -> Result<Vec<String>> {
+        let func = internal_api::get_api("_NodeListAttrNames".to_owned());
+        // the returned function gives the number of names for `-1` and the i-th name otherwise
+        let names = Function::try_from(call_packed!(func, self)?)?;
+        let size = i64::try_from(names.call(&[ArgValue::from(-1i64)])?)?;
+        (0..size)
+            .map(|i| String::try_from(names.call(&[ArgValue::from(i)])?))
+            .collect()
+    }
+
+    /// Gets the attribute `name` of the node.
+    pub fn get_attr(&self, name: &str) -> Result<RetValue> {
+        let func = internal_api::get_api("_NodeGetAttr".to_owned());
+        call_packed!(func, self, name)
+    }
+
+    /// Saves the node, together with the nodes it refers to, as JSON.
+    pub fn to_json(&self) -> Result<String> {
+        let func = internal_api::get_api("_save_json".to_owned());
+        String::try_from(call_packed!(func, self)?)
+    }
+
+    /// Loads a node saved with [`to_json`](#method.to_json).
+    pub fn from_json(json: &str) -> Result<NodeRef> {
+        let func = internal_api::get_api("_load_json".to_owned());
+        NodeRef::try_from(call_packed!(func, json)?)
+    }
+}
+
+#[cfg(feature = "compiler")]
+lazy_static! {
+    // type keys by type index, which are fixed once TVM is loaded.
+    static ref TYPE_KEYS: Mutex<HashMap<i32, String>> = Mutex::new(HashMap::new());
+}
+
+/// Finds the type key of the root node in the JSON written by `_save_json`, which
+/// looks like `{"root": 1, "nodes": [{"type_key": ""}, {"type_key": "Variable", ...}], ...}`.
+fn root_type_key(json: &str) -> Result<String> {
+    let json: Value = serde_json::from_str(json)?;
+    let key = json["root"]
+        .as_u64()
+        .and_then(|root| json["nodes"].get(root as usize))
+        .and_then(|node| node["type_key"].as_str());
+    match key {
+        Some(key) => Ok(key.to_owned()),
+        None => bail!(ErrorKind::InvalidFormat(
+            "cannot find the type key of the root node".to_owned()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_key() {
+        let json = r#"{
+          "root": 1,
+          "nodes": [
+            {
+              "type_key": ""
+            },
+            {
+              "type_key": "Variable",
+              "attrs": {
+                "name": "x, [y]",
+                "type": "int32"
+              }
+            }
+          ],
+          "b64ndarrays": [],
+          "attrs": {"tvm_version": "0.5"}
+        }"#;
+        assert_eq!(root_type_key(json).unwrap(), "Variable");
+        let first = json.replace("\"root\": 1", "\"root\": 0");
+        assert_eq!(root_type_key(&first).unwrap(), "");
+        let missing = json.replace("\"root\": 1", "\"root\": 2");
+        assert!(root_type_key(&missing).is_err());
+        let reordered = r#"{"nodes": [{"type_key": "\"root\": 0"}, {"type_key": "Var"}], "root": 1}"#;
+        assert_eq!(root_type_key(reordered).unwrap(), "Var");
+        assert!(root_type_key("{}").is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! Provides import and export of [`NDArray`] from and to the NumPy [`.npy`] format
+//! as well as reading named arrays from `.npz` archives with [`NpzReader`].
+//!
+//! NumPy dtype descriptors such as `<f4`, `>i8` or `|b1` are mapped onto [`TVMType`].
+//! Arrays stored with a byte order different from the host are converted when read.
+//!
+//! # Example
+//!
+//! ```
+//! let input = NDArray::from_npy(Path::new("input.npy")).unwrap();
+//! input.to_npy(Path::new("copy.npy")).unwrap();
+//!
+//! let mut npz = NpzReader::open(Path::new("dataset.npz")).unwrap();
+//! for array in npz {
+//!     let (name, nd) = array.unwrap();
+//!     println!("{}: {:?}", name, nd.shape());
+//! }
+//! ```
+//!
+//! [`.npy`]:https://docs.scipy.org/doc/numpy/neps/npy-format.html
+//! [`NpzReader`]:struct.NpzReader.html
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::Path,
+};
+
+use zip::ZipArchive;
+
+use ndarray::empty;
+use ErrorKind;
+use NDArray;
+use Result;
+use TVMContext;
+use TVMType;
+
+const NPY_MAGIC: &'static [u8] = b"\x93NUMPY";
+
+// the whole preamble including the header is padded to a multiple of this
+const NPY_ALIGNMENT: usize = 64;
+
+impl NDArray {
+    /// Writes the NDArray to a writer in the `.npy` format (version 1.0).
+    pub fn write_npy<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let shape = match self.shape() {
+            Some(shape) => shape.to_vec(),
+            None => bail!("{}", ErrorKind::EmptyArray),
+        };
+        let header = Header {
+            descr: dtype_to_descr(&self.dtype())?,
+            fortran_order: false,
+            shape: shape,
+        };
+        let data = self.to_bytes()?;
+        write_header(writer, &header)?;
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Reads an NDArray in cpu from a reader containing `.npy` data.
+    pub fn read_npy<R: Read>(reader: &mut R) -> Result<NDArray> {
+        let mut header = read_header(reader)?;
+        if header.fortran_order {
+            bail!(ErrorKind::InvalidFormat(
+                "fortran ordered arrays are not supported".to_owned()
+            ));
+        }
+        let (dtype, swap) = descr_to_dtype(&header.descr)?;
+        let itemsize = dtype.itemsize();
+        let nbytes = header.shape.iter().product::<usize>() * itemsize;
+        let mut data = vec![0u8; nbytes];
+        reader.read_exact(&mut data)?;
+        if swap {
+            for elem in data.chunks_mut(itemsize) {
+                elem.reverse();
+            }
+        }
+        let mut nd = empty(&mut header.shape, TVMContext::cpu(0), dtype);
+        nd.copy_from_bytes(&data);
+        Ok(nd)
+    }
+
+    /// Loads an NDArray in cpu from a `.npy` file.
+    pub fn from_npy(path: &Path) -> Result<NDArray> {
+        let mut reader = BufReader::new(File::open(path)?);
+        NDArray::read_npy(&mut reader)
+    }
+
+    /// Saves the NDArray to a `.npy` file.
+    pub fn to_npy(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_npy(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reader of `.npz` archives as written by `numpy.savez` and `numpy.savez_compressed`.
+///
+/// Arrays can be read by name or by iterating over the reader which yields
+/// `(name, NDArray)` pairs in the order they are stored in the archive.
+///
+/// ## Example
+///
+/// ```
+/// let mut npz = NpzReader::open(Path::new("dataset.npz")).unwrap();
+/// assert!(npz.names().contains(&"data".to_owned()));
+/// let data = npz.by_name("data").unwrap();
+/// ```
+pub struct NpzReader<R: Read + Seek> {
+    archive: ZipArchive<R>,
+    // position of the next array yielded by the iterator
+    next: usize,
+}
+
+impl NpzReader<BufReader<File>> {
+    /// Opens an `.npz` file.
+    pub fn open(path: &Path) -> Result<Self> {
+        NpzReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> NpzReader<R> {
+    /// Creates an `.npz` reader from a seekable reader.
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(NpzReader {
+            archive: ZipArchive::new(reader)?,
+            next: 0,
+        })
+    }
+
+    /// Returns the number of arrays in the archive.
+    pub fn len(&self) -> usize {
+        self.archive.len()
+    }
+
+    /// Returns true if the archive contains no arrays.
+    pub fn is_empty(&self) -> bool {
+        self.archive.len() == 0
+    }
+
+    /// Returns the names of the arrays in the archive.
+    pub fn names(&mut self) -> Result<Vec<String>> {
+        let mut names = Vec::with_capacity(self.archive.len());
+        for i in 0..self.archive.len() {
+            let file = self.archive.by_index(i)?;
+            names.push(array_name(file.name()));
+        }
+        Ok(names)
+    }
+
+    /// Reads the array with the given name into cpu.
+    pub fn by_name(&mut self, name: &str) -> Result<NDArray> {
+        for i in 0..self.archive.len() {
+            let mut file = self.archive.by_index(i)?;
+            if array_name(file.name()) == name {
+                return NDArray::read_npy(&mut file);
+            }
+        }
+        bail!(ErrorKind::InvalidFormat(format!(
+            "array `{}` not found in npz archive",
+            name
+        )))
+    }
+}
+
+impl<R: Read + Seek> Iterator for NpzReader<R> {
+    type Item = Result<(String, NDArray)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.archive.len() {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+        let mut file = match self.archive.by_index(index) {
+            Ok(file) => file,
+            Err(err) => return Some(Err(err.into())),
+        };
+        let name = array_name(file.name());
+        Some(NDArray::read_npy(&mut file).map(|nd| (name, nd)))
+    }
+}
+
+// `numpy.savez` stores each array as `<name>.npy`.
+fn array_name(file_name: &str) -> String {
+    file_name.trim_end_matches(".npy").to_owned()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+fn write_header<W: Write>(writer: &mut W, header: &Header) -> Result<()> {
+    let shape = match header.shape.len() {
+        0 => "()".to_owned(),
+        1 => format!("({},)", header.shape[0]),
+        _ => format!(
+            "({})",
+            header
+                .shape
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+        header.descr,
+        if header.fortran_order { "True" } else { "False" },
+        shape
+    );
+    // magic, version and header length take 10 bytes and the header ends with `\n`
+    let unpadded = NPY_MAGIC.len() + 4 + dict.len() + 1;
+    let padding = (NPY_ALIGNMENT - unpadded % NPY_ALIGNMENT) % NPY_ALIGNMENT;
+    dict.extend((0..padding).map(|_| ' '));
+    dict.push('\n');
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1u8, 0u8])?;
+    writer.write_all(&(dict.len() as u16).to_le_bytes())?;
+    writer.write_all(dict.as_bytes())?;
+    Ok(())
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<Header> {
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic[..] != NPY_MAGIC {
+        bail!(ErrorKind::InvalidFormat("invalid npy magic string".to_owned()));
+    }
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let header_len = match version[0] {
+        1 => {
+            let mut buf = [0u8; 2];
+            reader.read_exact(&mut buf)?;
+            u16::from_le_bytes(buf) as usize
+        }
+        2 | 3 => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            u32::from_le_bytes(buf) as usize
+        }
+        major => bail!(ErrorKind::InvalidFormat(format!(
+            "unsupported npy version {}.{}",
+            major, version[1]
+        ))),
+    };
+    let mut buf = vec![0u8; header_len];
+    reader.read_exact(&mut buf)?;
+    let header = match String::from_utf8(buf) {
+        Ok(header) => header,
+        Err(_) => bail!(ErrorKind::InvalidFormat(
+            "npy header is not valid UTF-8".to_owned()
+        )),
+    };
+    parse_header(&header)
+}
+
+fn parse_header(header: &str) -> Result<Header> {
+    let descr = {
+        let value = header_value(header, "descr")?;
+        let quote = match value.chars().next() {
+            Some(q) if q == '\'' || q == '"' => q,
+            _ => bail!(ErrorKind::InvalidFormat(format!(
+                "invalid `descr` in npy header {:?}",
+                header
+            ))),
+        };
+        match value[1..].find(quote) {
+            Some(end) => value[1..end + 1].to_owned(),
+            None => bail!(ErrorKind::InvalidFormat(format!(
+                "invalid `descr` in npy header {:?}",
+                header
+            ))),
+        }
+    };
+    let fortran_order = {
+        let value = header_value(header, "fortran_order")?;
+        if value.starts_with("True") {
+            true
+        } else if value.starts_with("False") {
+            false
+        } else {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "invalid `fortran_order` in npy header {:?}",
+                header
+            )));
+        }
+    };
+    let shape = {
+        let value = header_value(header, "shape")?;
+        let end = match (value.starts_with('('), value.find(')')) {
+            (true, Some(end)) => end,
+            _ => bail!(ErrorKind::InvalidFormat(format!(
+                "invalid `shape` in npy header {:?}",
+                header
+            ))),
+        };
+        let mut shape = Vec::new();
+        for dim in value[1..end].split(',') {
+            // python 2 may write long integers such as `3L`
+            let dim = dim.trim().trim_end_matches('L');
+            if dim.is_empty() {
+                continue;
+            }
+            match dim.parse::<usize>() {
+                Ok(dim) => shape.push(dim),
+                Err(_) => bail!(ErrorKind::InvalidFormat(format!(
+                    "invalid dimension {:?} in npy header",
+                    dim
+                ))),
+            }
+        }
+        shape
+    };
+    Ok(Header {
+        descr,
+        fortran_order,
+        shape,
+    })
+}
+
+// Returns the remainder of the header right after `'key':`.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let pattern = format!("'{}'", key);
+    let start = match header.find(&pattern) {
+        Some(start) => start + pattern.len(),
+        None => bail!(ErrorKind::InvalidFormat(format!(
+            "missing `{}` in npy header",
+            key
+        ))),
+    };
+    let rest = header[start..].trim_start();
+    if !rest.starts_with(':') {
+        bail!(ErrorKind::InvalidFormat(format!(
+            "missing value of `{}` in npy header",
+            key
+        )));
+    }
+    Ok(rest[1..].trim_start())
+}
+
+// Maps a NumPy dtype descriptor to `TVMType`, also returning whether the data
+// byte order differs from the host and has to be swapped.
+fn descr_to_dtype(descr: &str) -> Result<(TVMType, bool)> {
+    let invalid = || ErrorKind::InvalidFormat(format!("unsupported npy dtype {:?}", descr));
+    let mut chars = descr.chars();
+    let order = match chars.next() {
+        Some(order) => order,
+        None => bail!(invalid()),
+    };
+    let kind = match chars.next() {
+        Some(kind) => kind,
+        None => bail!(invalid()),
+    };
+    let bytes = match chars.as_str().parse::<u8>() {
+        Ok(bytes) => bytes,
+        Err(_) => bail!(invalid()),
+    };
+    let dtype = match (kind, bytes) {
+        // numpy bool is stored in a byte which matches TVM's `uint1`
+        ('b', 1) => TVMType::new(1, 1, 1),
+        ('i', 1) | ('i', 2) | ('i', 4) | ('i', 8) => TVMType::new(0, bytes * 8, 1),
+        ('u', 1) | ('u', 2) | ('u', 4) | ('u', 8) => TVMType::new(1, bytes * 8, 1),
+        ('f', 2) | ('f', 4) | ('f', 8) => TVMType::new(2, bytes * 8, 1),
+        _ => bail!(invalid()),
+    };
+    let swap = match order {
+        '|' if bytes == 1 => false,
+        '=' => false,
+        '<' => cfg!(target_endian = "big"),
+        '>' => cfg!(target_endian = "little"),
+        _ => bail!(invalid()),
+    } && bytes > 1;
+    Ok((dtype, swap))
+}
+
+// Maps `TVMType` to a NumPy dtype descriptor in host byte order.
+fn dtype_to_descr(dtype: &TVMType) -> Result<String> {
+    let unsupported = || ErrorKind::InvalidFormat(format!("dtype {} has no npy equivalent", dtype));
+    if dtype.lanes != 1 {
+        bail!(unsupported());
+    }
+    let (kind, bytes) = match (dtype.code, dtype.bits) {
+        (1, 1) => ('b', 1),
+        (0, 8) | (0, 16) | (0, 32) | (0, 64) => ('i', dtype.bits / 8),
+        (1, 8) | (1, 16) | (1, 32) | (1, 64) => ('u', dtype.bits / 8),
+        (2, 16) | (2, 32) | (2, 64) => ('f', dtype.bits / 8),
+        _ => bail!(unsupported()),
+    };
+    let order = if bytes == 1 {
+        '|'
+    } else if cfg!(target_endian = "little") {
+        '<'
+    } else {
+        '>'
+    };
+    Ok(format!("{}{}{}", order, kind, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let header = Header {
+            descr: "<f4".to_owned(),
+            fortran_order: false,
+            shape: vec![2, 3],
+        };
+        let mut buf = Vec::new();
+        write_header(&mut buf, &header).unwrap();
+        assert_eq!(buf.len() % NPY_ALIGNMENT, 0);
+        assert_eq!(read_header(&mut &buf[..]).unwrap(), header);
+        let parsed =
+            parse_header("{'descr': '|u1', 'fortran_order': True, 'shape': (3L,), }").unwrap();
+        assert_eq!(parsed.descr, "|u1");
+        assert!(parsed.fortran_order);
+        assert_eq!(parsed.shape, vec![3]);
+        assert!(parse_header("{'descr': '<f4', 'shape': (3,), }").is_err());
+    }
+
+    #[test]
+    fn descr() {
+        let big_endian = cfg!(target_endian = "big");
+        assert_eq!(
+            descr_to_dtype("<f4").unwrap(),
+            (TVMType::from("float"), big_endian)
+        );
+        assert_eq!(
+            descr_to_dtype("|u1").unwrap(),
+            (TVMType::new(1, 8, 1), false)
+        );
+        assert_eq!(
+            descr_to_dtype(">i8").unwrap(),
+            (TVMType::new(0, 64, 1), !big_endian)
+        );
+        assert_eq!(
+            descr_to_dtype("|b1").unwrap(),
+            (TVMType::new(1, 1, 1), false)
+        );
+        assert!(descr_to_dtype("<c8").is_err());
+        assert!(descr_to_dtype("|i4").is_err());
+        let host_order = if big_endian { ">" } else { "<" };
+        assert_eq!(
+            dtype_to_descr(&TVMType::from("int")).unwrap(),
+            format!("{}i4", host_order)
+        );
+        assert_eq!(dtype_to_descr(&TVMType::new(1, 1, 1)).unwrap(), "|b1");
+        assert!(dtype_to_descr(&TVMType::new(2, 32, 4)).is_err());
+    }
+
+    #[test]
+    fn npz() {
+        use std::io::Cursor;
+        use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+        let shape = &mut [3];
+        let mut data = vec![1f32, 2., 3.];
+        let mut ndarray = empty(shape, TVMContext::cpu(0), TVMType::from("float"));
+        ndarray.copy_from_buffer(&mut data);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        for name in &["a.npy", "b.npy"] {
+            zip.start_file(*name, options).unwrap();
+            ndarray.write_npy(&mut zip).unwrap();
+        }
+        let buf = zip.finish().unwrap();
+        let mut npz = NpzReader::new(buf).unwrap();
+        assert_eq!(npz.len(), 2);
+        assert!(!npz.is_empty());
+        assert_eq!(npz.names().unwrap(), vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(npz.by_name("b").unwrap().to_vec::<f32>().unwrap(), data);
+        assert!(npz.by_name("c").is_err());
+        let arrays = npz.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(arrays.len(), 2);
+        assert_eq!(arrays[0].0, "a");
+    }
+
+    #[test]
+    fn big_endian() {
+        let mut buf = Vec::new();
+        let header = Header {
+            descr: ">i4".to_owned(),
+            fortran_order: false,
+            shape: vec![2],
+        };
+        write_header(&mut buf, &header).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 1, 0, 0, 1, 0]);
+        let nd = NDArray::read_npy(&mut &buf[..]).unwrap();
+        assert_eq!(nd.to_vec::<i32>().unwrap(), vec![1, 256]);
+    }
+
+    #[test]
+    fn npy() {
+        let shape = &mut [2, 2];
+        let mut data = vec![1i32, 2, 3, 4];
+        let mut ndarray = empty(shape, TVMContext::cpu(0), TVMType::from("int"));
+        ndarray.copy_from_buffer(&mut data);
+        let mut buf = Vec::new();
+        ndarray.write_npy(&mut buf).unwrap();
+        let loaded = NDArray::read_npy(&mut &buf[..]).unwrap();
+        assert_eq!(loaded.shape().unwrap(), shape);
+        assert_eq!(loaded.to_vec::<i32>().unwrap(), data);
+    }
+}
@@ -0,0 +1,16 @@
+//! This module provides the TVM RPC, which runs modules on remote devices such as boards.
+//!
+//! A [`Session`] connects to a TVM RPC server, uploads and loads modules on the server and
+//! creates contexts of the remote devices, so arrays are allocated on the remote devices
+//! and functions of the remote modules are called like local ones.
+//!
+//! A [`Server`] serves the devices of the process to the clients, directly or through
+//! an RPC tracker, without a Python process.
+//!
+//! *Note:* TVM must be built with `USE_RPC=1`, otherwise connecting returns an error.
+
+mod server;
+mod session;
+
+pub use self::server::Server;
+pub use self::session::Session;
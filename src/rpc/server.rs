@@ -0,0 +1,348 @@
+//! Server side of the TVM RPC, serving the local runtime to remote clients.
+
+use std::{
+    cell::RefCell,
+    collections::hash_map::RandomState,
+    convert::TryFrom,
+    env, fs,
+    hash::{BuildHasher, Hasher},
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use function;
+use internal_api;
+use ArgValue;
+use ErrorKind;
+use Module;
+use Result;
+use TVMArgValue;
+use TVMRetValue;
+
+const RPC_MAGIC: i32 = 0xff271;
+const RPC_CODE_SUCCESS: i32 = RPC_MAGIC;
+const RPC_CODE_MISMATCH: i32 = RPC_MAGIC + 2;
+const RPC_TRACKER_MAGIC: i32 = 0x2f271;
+
+// tracker request codes, see `TrackerCode` in TVM
+const TRACKER_SUCCESS: i32 = 0;
+const TRACKER_PUT: i32 = 3;
+const TRACKER_UPDATE_INFO: i32 = 5;
+
+// longest key or tracker reply accepted from a peer
+const MAX_MESSAGE_LEN: usize = 4096;
+
+thread_local! {
+    // working directory of the session served on this thread. The runtime calls the server
+    // functions on the thread running the server loop, so each server has its own.
+    static WORK_DIR: RefCell<PathBuf> = RefCell::new(env::temp_dir());
+}
+
+lazy_static! {
+    // outcome of registering the server functions, which is done once per process.
+    static ref SERVER_FUNCS: ::std::result::Result<(), String> = register_server_funcs()
+        .map_err(|err| err.to_string());
+}
+
+/// Returns the path in the working directory of the file named by the only argument of
+/// the server function `func`.
+fn work_path(func: &str, args: &[TVMArgValue]) -> Result<PathBuf> {
+    if args.len() != 1 {
+        bail!(ErrorKind::ArgumentCountMismatch(
+            func.to_owned(),
+            1,
+            args.len()
+        ));
+    }
+    let name = String::try_from(args[0].to_owned_value()?)?;
+    Ok(WORK_DIR.with(|work_dir| work_dir.borrow().join(name)))
+}
+
+fn server_workpath(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+    let path = work_path("tvm.rpc.server.workpath", args)?;
+    Ok(TVMRetValue::from(path.to_string_lossy().into_owned()))
+}
+
+fn server_load_module(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+    let module = Module::load(&work_path("tvm.rpc.server.load_module", args)?)?;
+    Ok(TVMRetValue::from(module))
+}
+
+/// Registers the functions the runtime requires from the server, which the Python
+/// server registers as well, e.g. to upload files and to load modules.
+fn register_server_funcs() -> Result<()> {
+    function::register(server_workpath, "tvm.rpc.server.workpath".to_owned(), true)?;
+    function::register(
+        server_load_module,
+        "tvm.rpc.server.load_module".to_owned(),
+        true,
+    )
+}
+
+/// Connection to an RPC tracker, to which the server reports its address.
+#[derive(Debug)]
+struct Tracker {
+    conn: TcpStream,
+    custom_addr: Option<String>,
+}
+
+/// TVM RPC server serving the devices of this process, one session at a time. Several
+/// servers can run in one process, each serving on its own thread.
+///
+/// ## Example
+///
+/// ```
+/// let mut server = rpc::Server::bind("0.0.0.0:9090")
+///     .unwrap()
+///     .key("board")
+///     .tracker("10.0.0.1:9190", None)
+///     .unwrap();
+/// server.serve().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    key: String,
+    work_dir: PathBuf,
+    tracker: Option<Tracker>,
+}
+
+impl Server {
+    /// Binds a server to `addr`, which accepts the clients with an empty key and uploads
+    /// the files of the clients to a temporary directory.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Server> {
+        let listener = TcpListener::bind(addr)?;
+        let port = listener.local_addr()?.port();
+        let work_dir = env::temp_dir().join(format!("tvm-rpc-{}-{}", process::id(), port));
+        Ok(Server {
+            listener,
+            key: String::new(),
+            work_dir,
+            tracker: None,
+        })
+    }
+
+    /// Sets the key which the clients, or the tracker, use to find the server.
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = key.to_owned();
+        self
+    }
+
+    /// Sets the directory where the files uploaded by the clients are stored.
+    pub fn work_dir<P: AsRef<Path>>(mut self, work_dir: P) -> Self {
+        self.work_dir = work_dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Registers the server to the RPC tracker at `addr`, which hands the server out to the
+    /// clients requesting its key. The server is reported with its bound address, or with
+    /// `custom_addr` if it is reachable at another address, e.g. behind a NAT.
+    pub fn tracker<A: ToSocketAddrs>(mut self, addr: A, custom_addr: Option<&str>) -> Result<Self> {
+        let mut conn = TcpStream::connect(addr)?;
+        write_i32(&mut conn, RPC_TRACKER_MAGIC)?;
+        if read_i32(&mut conn)? != RPC_TRACKER_MAGIC {
+            bail!(ErrorKind::RpcError("not a TVM RPC tracker".to_owned()));
+        }
+        let info = json!([TRACKER_UPDATE_INFO, { "key": format!("server:{}", self.key) }]);
+        tracker_request(&mut conn, &info.to_string())?;
+        self.tracker = Some(Tracker {
+            conn,
+            custom_addr: custom_addr.map(|addr| addr.to_owned()),
+        });
+        Ok(self)
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves the clients forever. A failed session is logged to stderr and only closes its
+    /// connection, while errors of the listener or the tracker are returned.
+    pub fn serve(&mut self) -> Result<()> {
+        loop {
+            let conn = self.accept()?;
+            if let Err(err) = self.serve_session(conn) {
+                eprintln!("TVM RPC session failed: {}", err);
+            }
+        }
+    }
+
+    /// Accepts a client and serves it until it disconnects.
+    pub fn serve_one(&mut self) -> Result<()> {
+        let conn = self.accept()?;
+        self.serve_session(conn)
+    }
+
+    /// Accepts the next client which passes the handshake.
+    fn accept(&mut self) -> Result<TcpStream> {
+        loop {
+            let match_key = match self.tracker {
+                Some(ref mut tracker) => {
+                    // a new key per session, since the tracker hands out a key only once
+                    let match_key = format!("{}:{}", self.key, random_suffix());
+                    let port = self.listener.local_addr()?.port();
+                    let put = json!([
+                        TRACKER_PUT,
+                        self.key,
+                        [port, match_key],
+                        tracker.custom_addr
+                    ]);
+                    tracker_request(&mut tracker.conn, &put.to_string())?;
+                    match_key
+                }
+                None => self.key.clone(),
+            };
+            loop {
+                let (mut conn, _) = self.listener.accept()?;
+                // a client failing the handshake is dropped
+                match handshake(&mut conn, &match_key, &self.key) {
+                    Ok(true) => return Ok(conn),
+                    Ok(false) if self.tracker.is_some() => break,
+                    _ => continue,
+                }
+            }
+        }
+    }
+
+    fn serve_session(&self, conn: TcpStream) -> Result<()> {
+        if let Err(ref msg) = *SERVER_FUNCS {
+            bail!(ErrorKind::RpcError(format!(
+                "failed to register the server functions: {}",
+                msg
+            )));
+        }
+        fs::create_dir_all(&self.work_dir)?;
+        WORK_DIR.with(|work_dir| *work_dir.borrow_mut() = self.work_dir.clone());
+        let server_loop = internal_api::try_get_api("rpc._ServerLoop")?;
+        // the runtime closes the socket at the end of the session
+        let sock = into_raw_socket(conn);
+        server_loop.call(&[ArgValue::from(sock)])?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn into_raw_socket(conn: TcpStream) -> i64 {
+    use std::os::unix::io::IntoRawFd;
+    i64::from(conn.into_raw_fd())
+}
+
+#[cfg(windows)]
+fn into_raw_socket(conn: TcpStream) -> i64 {
+    use std::os::windows::io::IntoRawSocket;
+    conn.into_raw_socket() as i64
+}
+
+/// Performs the server side of the handshake, returning whether the key matches.
+///
+/// The session runs inside `rpc._ServerLoop` until the client disconnects, so the
+/// ` -timeout=N` option a client may send after its key is ignored.
+fn handshake(conn: &mut TcpStream, match_key: &str, key: &str) -> Result<bool> {
+    if read_i32(conn)? != RPC_MAGIC {
+        bail!(ErrorKind::RpcError("not a TVM RPC client".to_owned()));
+    }
+    let client_key = read_message(conn)?;
+    let client_key = String::from_utf8_lossy(&client_key);
+    // options such as ` -timeout=10` follow the key and are ignored
+    let header = client_key.split_whitespace().next().unwrap_or("");
+    if header != format!("client:{}", match_key) {
+        write_i32(conn, RPC_CODE_MISMATCH)?;
+        return Ok(false);
+    }
+    let server_key = format!("server:{}", key);
+    write_i32(conn, RPC_CODE_SUCCESS)?;
+    write_i32(conn, server_key.len() as i32)?;
+    conn.write_all(server_key.as_bytes())?;
+    Ok(true)
+}
+
+/// Sends a JSON request to the tracker and checks that it succeeds.
+fn tracker_request(conn: &mut TcpStream, request: &str) -> Result<()> {
+    write_i32(conn, request.len() as i32)?;
+    conn.write_all(request.as_bytes())?;
+    let reply = read_message(conn)?;
+    let reply = String::from_utf8_lossy(&reply);
+    if reply.trim().parse::<i32>() != Ok(TRACKER_SUCCESS) {
+        bail!(ErrorKind::RpcError(format!(
+            "tracker rejected `{}` with `{}`",
+            request, reply
+        )));
+    }
+    Ok(())
+}
+
+fn read_i32(conn: &mut TcpStream) -> Result<i32> {
+    let mut bytes = [0; 4];
+    conn.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+/// Reads a message prefixed by its length, which is at most `MAX_MESSAGE_LEN` bytes.
+fn read_message(conn: &mut TcpStream) -> Result<Vec<u8>> {
+    let len = read_i32(conn)?;
+    if len < 0 || len as usize > MAX_MESSAGE_LEN {
+        bail!(ErrorKind::RpcError(format!("invalid message length {}", len)));
+    }
+    let mut message = vec![0; len as usize];
+    conn.read_exact(&mut message)?;
+    Ok(message)
+}
+
+fn write_i32(conn: &mut TcpStream, value: i32) -> Result<()> {
+    conn.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn random_suffix() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::Session;
+    use std::thread;
+
+    #[test]
+    fn loopback() {
+        let mut server = Server::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let serving = thread::spawn(move || server.serve_one());
+
+        {
+            let sess = Session::connect("127.0.0.1", port, "").unwrap();
+            sess.upload(b"tvm", "data.bin").unwrap();
+            assert_eq!(sess.download("data.bin").unwrap(), b"tvm");
+            sess.remove("data.bin").unwrap();
+        }
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn server_func_args() {
+        assert!(server_workpath(&[]).is_err());
+        let num = ArgValue::from(1);
+        assert!(server_workpath(&[num.to_raw().unwrap()]).is_err());
+        let name = ArgValue::from("lib.so");
+        let path = server_workpath(&[name.to_raw().unwrap()]).unwrap();
+        assert!(path.to_string().ends_with("lib.so"));
+    }
+
+    #[test]
+    fn key_length() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut conn, _) = listener.accept().unwrap();
+        write_i32(&mut client, RPC_MAGIC).unwrap();
+        write_i32(&mut client, i32::MAX).unwrap();
+        assert!(handshake(&mut conn, "", "").is_err());
+    }
+}
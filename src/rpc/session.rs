@@ -0,0 +1,122 @@
+//! Client side of the TVM RPC.
+
+use std::{convert::TryFrom, time::Duration};
+
+use context::{TVMContext, TVMDeviceType};
+use function::Function;
+use internal_api;
+use Module;
+use Result;
+
+/// Session with a TVM RPC server. The connection is closed once the session and the remote
+/// modules, functions and arrays obtained from it are dropped.
+///
+/// ## Example
+///
+/// ```
+/// let sess = rpc::Session::connect("127.0.0.1", 9090, "").unwrap();
+/// sess.upload(&fs::read("add_cpu.so").unwrap(), "add_cpu.so").unwrap();
+/// let mut fadd = sess.load_module("add_cpu.so").unwrap();
+/// fadd.entry_func();
+/// let ctx = sess.context(TVMDeviceType::Cpu, 0).unwrap();
+/// let arr = empty(&mut [2], ctx, TVMType::from("float"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    module: Module,
+    index: usize,
+}
+
+impl Session {
+    /// Connects to the RPC server at `host:port`. The `key` is matched by the server,
+    /// or by the tracker it is registered to, and can be empty.
+    pub fn connect(host: &str, port: u16, key: &str) -> Result<Session> {
+        Session::connect_key(host, port, key.to_owned())
+    }
+
+    /// Connects to the RPC server at `host:port` like [`connect`], and asks the server to
+    /// close the session after `timeout`. Servers such as the Python one honour it, while
+    /// the Rust [`Server`](struct.Server.html) ignores it and serves until the client
+    /// disconnects.
+    ///
+    /// [`connect`]:struct.Session.html#method.connect
+    pub fn connect_with_timeout(
+        host: &str,
+        port: u16,
+        key: &str,
+        timeout: Duration,
+    ) -> Result<Session> {
+        Session::connect_key(
+            host,
+            port,
+            format!("{} -timeout={}", key, timeout.as_secs()),
+        )
+    }
+
+    fn connect_key(host: &str, port: u16, key: String) -> Result<Session> {
+        let connect = internal_api::try_get_api("rpc._Connect")?;
+        let ret = call_packed!(connect, host, &port, &key)?;
+        Session::from_module(Module::try_from(ret)?)
+    }
+
+    /// Wraps the module of an RPC session.
+    pub fn from_module(module: Module) -> Result<Session> {
+        let sess_index = internal_api::try_get_api("rpc._SessTableIndex")?;
+        let index = call_packed!(sess_index, &module)?.to_int() as usize;
+        Ok(Session { module, index })
+    }
+
+    /// Returns the underlying RPC module.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Returns the index of the session, which is encoded in the device types of its contexts.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets a global function of the server.
+    pub fn get_function(&self, name: &str) -> Result<Function> {
+        self.module.get_function(name, false)
+    }
+
+    /// Uploads `data` to `path` which is relative to the working directory of the server.
+    pub fn upload(&self, data: &[u8], path: &str) -> Result<()> {
+        let upload = self.get_function("tvm.rpc.server.upload")?;
+        call_packed!(upload, path, data)?;
+        Ok(())
+    }
+
+    /// Downloads the file at `path` which is relative to the working directory of the server.
+    pub fn download(&self, path: &str) -> Result<Vec<u8>> {
+        let download = self.get_function("tvm.rpc.server.download")?;
+        let ret = call_packed!(download, path)?;
+        Vec::<u8>::try_from(ret)
+    }
+
+    /// Removes the file at `path` which is relative to the working directory of the server.
+    pub fn remove(&self, path: &str) -> Result<()> {
+        let remove = self.get_function("tvm.rpc.server.remove")?;
+        call_packed!(remove, path)?;
+        Ok(())
+    }
+
+    /// Loads a module uploaded to `path` on the server.
+    pub fn load_module(&self, path: &str) -> Result<Module> {
+        let load = internal_api::try_get_api("rpc._LoadRemoteModule")?;
+        let ret = call_packed!(load, &self.module, path)?;
+        Module::try_from(ret)
+    }
+
+    /// Creates the context of a device of the server, e.g. to allocate an array on it with
+    /// [`empty`](../ndarray/fn.empty.html). Returns an error if the device type code is out
+    /// of range.
+    pub fn context(&self, device_type: TVMDeviceType, device_id: usize) -> Result<TVMContext> {
+        let device = match device_type {
+            TVMDeviceType::Rpc { device, .. } => device,
+            local => usize::from(local),
+        };
+        Ok(TVMContext::new(TVMDeviceType::rpc(self.index, device)?, device_id))
+    }
+}
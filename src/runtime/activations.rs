@@ -0,0 +1,93 @@
+//! Provides the [`ActivationRuntime`] returning the intermediate outputs of a graph.
+
+use std::ops::Deref;
+
+use super::graph::{Graph, GraphRuntime, NodeEntry};
+use Module;
+use NDArray;
+use Result;
+use TVMContext;
+
+/// Graph runtime which outputs the activations of selected nodes besides the outputs of the
+/// graph, e.g. to compare them to the reference framework layer by layer.
+///
+/// It dereferences to a [`GraphRuntime`] of the rewritten graph, whose first outputs are
+/// the outputs of the original graph.
+///
+/// ## Example
+///
+/// ```
+/// let graph = Graph::from_json(&graph_json).unwrap();
+/// let runtime = ActivationRuntime::create(&graph, &["conv2d0", "dense0"], &lib, &ctx, &params)
+///     .unwrap();
+/// runtime.set_input("data", &input).unwrap();
+/// for (name, activation) in runtime.activations().unwrap() {
+///     println!("{}: {}", name, activation);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ActivationRuntime {
+    runtime: GraphRuntime,
+    // name of each activation and its index in the outputs of the runtime
+    outputs: Vec<(String, usize)>,
+}
+
+impl ActivationRuntime {
+    /// Creates a runtime of `graph` which also outputs the nodes named `names`, with the
+    /// operators of `lib` on `ctx` and the parameters `params` of the original graph.
+    pub fn create(
+        graph: &Graph,
+        names: &[&str],
+        lib: &Module,
+        ctx: &TVMContext,
+        params: &[u8],
+    ) -> Result<ActivationRuntime> {
+        let (rewritten, entries) = graph.with_outputs(names)?;
+        let runtime = GraphRuntime::create(&rewritten.to_json()?, lib, ctx)?;
+        runtime.load_params(params)?;
+        let outputs = entries
+            .into_iter()
+            .map(|(entry, output)| (activation_name(graph, &entry), output))
+            .collect();
+        Ok(ActivationRuntime { runtime, outputs })
+    }
+
+    /// Returns the names of the activations, which are the node names, followed by
+    /// `____<index>` for the nodes with several outputs as in the debug format of TVM.
+    pub fn names(&self) -> Vec<&str> {
+        self.outputs
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Runs the graph and returns the named activations, copied to cpu.
+    pub fn activations(&self) -> Result<Vec<(String, NDArray)>> {
+        self.runtime.run()?;
+        let cpu = TVMContext::cpu(0);
+        self.outputs
+            .iter()
+            .map(|&(ref name, output)| {
+                let activation = self.runtime.get_output(output)?.copy_to_ctx(&cpu)?;
+                Ok((name.clone(), activation))
+            })
+            .collect()
+    }
+}
+
+impl Deref for ActivationRuntime {
+    type Target = GraphRuntime;
+
+    fn deref(&self) -> &GraphRuntime {
+        &self.runtime
+    }
+}
+
+fn activation_name(graph: &Graph, entry: &NodeEntry) -> String {
+    let node = &graph.nodes[entry.node_id];
+    if node.num_outputs() == 1 {
+        node.name.clone()
+    } else {
+        format!("{}____{}", node.name, entry.index)
+    }
+}
@@ -0,0 +1,402 @@
+//! Provides the [`DebugGraphRuntime`] profiling a graph operator by operator.
+
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fs::{self, File},
+    io::BufWriter,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{self, Value};
+
+use super::graph::{create_runtime_module, Graph, GraphRuntime, Node, NodeEntry};
+use ndarray;
+use ErrorKind;
+use Module;
+use NDArray;
+use Result;
+use TVMContext;
+
+/// Graph runtime which runs the graph node by node to time each node and to keep its
+/// outputs, wrapping the module created by `tvm.graph_runtime_debug.create`.
+///
+/// It dereferences to a [`GraphRuntime`] to set the inputs and the parameters.
+///
+/// ## Example
+///
+/// ```
+/// let runtime = DebugGraphRuntime::create(&graph_json, &lib, &TVMContext::cpu(0)).unwrap();
+/// runtime.load_params(&params).unwrap();
+/// runtime.set_input("data", &input).unwrap();
+/// let result = runtime.profile(10, 1, 0).unwrap();
+/// println!("{}", result.table());
+/// result.dump("/tmp/tvmdbg").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebugGraphRuntime {
+    runtime: GraphRuntime,
+    graph: Graph,
+    ctx: TVMContext,
+}
+
+impl DebugGraphRuntime {
+    /// Creates a debug runtime of the graph `graph_json` with the operators of `lib` on `ctx`.
+    /// TVM must be built with `USE_GRAPH_RUNTIME_DEBUG=1`.
+    pub fn create(graph_json: &str, lib: &Module, ctx: &TVMContext) -> Result<DebugGraphRuntime> {
+        let graph = Graph::from_json(graph_json)?;
+        let module = create_runtime_module("tvm.graph_runtime_debug.create", graph_json, lib, ctx)?;
+        Ok(DebugGraphRuntime {
+            runtime: GraphRuntime::from_module(module),
+            graph,
+            ctx: ctx.clone(),
+        })
+    }
+
+    /// Returns the graph being run.
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Runs the graph node by node and returns the time and the outputs of each node.
+    ///
+    /// Like [`Module::time_evaluator`](../module/struct.Module.html#method.time_evaluator),
+    /// each node is timed over `number` runs, increased until the runs of a repeat take at
+    /// least `min_repeat_ms` milliseconds, and averaged over `repeat` repeats.
+    pub fn profile(
+        &self,
+        number: usize,
+        repeat: usize,
+        min_repeat_ms: usize,
+    ) -> Result<DebugResult> {
+        let run_individual = self.runtime.get_function("run_individual")?;
+        let ret = call_packed!(run_individual, &number, &repeat, &min_repeat_ms)?;
+        // microseconds per node, separated by commas
+        let times = String::try_from(ret)?;
+        let times = times
+            .split(',')
+            .filter(|time| !time.trim().is_empty())
+            .map(|time| {
+                time.trim().parse::<f64>().map(|us| us * 1e-6).map_err(|_| {
+                    ErrorKind::InvalidFormat(format!("invalid node time `{}`", time)).into()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if times.len() != self.graph.nodes.len() {
+            bail!(ErrorKind::InvalidFormat(format!(
+                "expected the times of {} nodes, but found {}",
+                self.graph.nodes.len(),
+                times.len()
+            )));
+        }
+
+        let get_output_by_layer = self.runtime.get_function("get_output_by_layer")?;
+        let cpu = TVMContext::cpu(0);
+        let mut nodes = Vec::with_capacity(times.len());
+        for (node_id, (node, time)) in self.graph.nodes.iter().zip(times).enumerate() {
+            let mut outputs = Vec::with_capacity(node.num_outputs());
+            for index in 0..node.num_outputs() {
+                let get_output = get_output_by_layer.clone();
+                let ret = call_packed!(get_output, &node_id, &index)?;
+                // the output is overwritten by the next run
+                outputs.push(NDArray::try_from(ret)?.copy_to_ctx(&cpu)?);
+            }
+            nodes.push(NodeProfile {
+                node_id,
+                name: node.name.clone(),
+                op: op_name(node).to_owned(),
+                time,
+                outputs,
+            });
+        }
+        Ok(DebugResult {
+            nodes,
+            graph: self.graph.clone(),
+            ctx: self.ctx.clone(),
+        })
+    }
+}
+
+impl Deref for DebugGraphRuntime {
+    type Target = GraphRuntime;
+
+    fn deref(&self) -> &GraphRuntime {
+        &self.runtime
+    }
+}
+
+/// Returns the name of the operator of a node, which is `param` for the inputs and the
+/// parameters of the graph, as in the debug format of TVM.
+fn op_name(node: &Node) -> &str {
+    if node.is_param() {
+        "param"
+    } else {
+        node.attr("func_name").unwrap_or(&node.op)
+    }
+}
+
+/// Time and outputs of a node of the graph.
+#[derive(Debug)]
+pub struct NodeProfile {
+    pub node_id: usize,
+    pub name: String,
+    /// Name of the operator, or `param` for the inputs and the parameters.
+    pub op: String,
+    /// Mean time of the node in seconds.
+    pub time: f64,
+    /// Outputs of the node, copied to cpu.
+    pub outputs: Vec<NDArray>,
+}
+
+/// Profile of a run of a [`DebugGraphRuntime`].
+#[derive(Debug)]
+pub struct DebugResult {
+    /// Profiles of the nodes in the order of the graph.
+    pub nodes: Vec<NodeProfile>,
+    graph: Graph,
+    ctx: TVMContext,
+}
+
+impl DebugResult {
+    /// Returns the total time of the nodes in seconds.
+    pub fn total_time(&self) -> f64 {
+        self.nodes.iter().map(|node| node.time).sum()
+    }
+
+    /// Formats the operators sorted by their time, from the slowest one, in the columns
+    /// of the table printed by the debug runtime of TVM in Python, one row per node with
+    /// the shape of its first output.
+    pub fn table(&self) -> String {
+        let total_time = self.total_time();
+        let header = [
+            "Node Name",
+            "Ops",
+            "Time(us)",
+            "Time(%)",
+            "Shape",
+            "Inputs",
+            "Outputs",
+        ];
+        let mut ops: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|node| node.op != "param")
+            .collect();
+        ops.sort_by(|a, b| b.time.partial_cmp(&a.time).unwrap_or(Ordering::Equal));
+        let rows: Vec<_> = ops
+            .into_iter()
+            .map(|profile| {
+                let node = &self.graph.nodes[profile.node_id];
+                let percent = if total_time > 0.0 {
+                    profile.time / total_time * 100.0
+                } else {
+                    0.0
+                };
+                let entry_id = self.graph.entry_id(&NodeEntry::new(profile.node_id, 0));
+                let shape = self
+                    .graph
+                    .entry_attr("shape", entry_id)
+                    .and_then(|shape| serde_json::from_value::<Vec<usize>>(shape.clone()).ok())
+                    .map(|shape| format!("{:?}", shape))
+                    .unwrap_or_default();
+                vec![
+                    profile.name.clone(),
+                    profile.op.clone(),
+                    format!("{:.3}", profile.time * 1e6),
+                    format!("{:.3}", percent),
+                    shape,
+                    node.attr("num_inputs").unwrap_or("").to_owned(),
+                    node.attr("num_outputs").unwrap_or("").to_owned(),
+                ]
+            })
+            .collect();
+        let widths: Vec<_> = header
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                rows.iter()
+                    .map(|row| row[i].len())
+                    .chain(Some(title.len()))
+                    .max()
+                    .unwrap_or(0)
+                    + 2
+            })
+            .collect();
+        let format_row = |row: &[&str]| {
+            let line: String = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            line.trim_end().to_owned() + "\n"
+        };
+        let mut table = format_row(&header);
+        table += &format_row(&[
+            "---------",
+            "---",
+            "--------",
+            "-------",
+            "-----",
+            "------",
+            "-------",
+        ]);
+        for row in &rows {
+            let row: Vec<&str> = row.iter().map(|cell| cell.as_str()).collect();
+            table += &format_row(&row);
+        }
+        table
+    }
+
+    /// Dumps the result in the debug format of TVM to the directory `_tvmdbg_ctx_<ctx>`
+    /// under `dump_root` and returns the directory. It contains the graph with the node
+    /// shapes and types in `_tvmdbg_graph_dump.json`, the node outputs named
+    /// `<node name>____<output index>` in `output_tensors.params` and a trace viewable
+    /// in Chrome in `_tvmdbg_execution_trace.json`.
+    pub fn dump<P: AsRef<Path>>(&self, dump_root: P) -> Result<PathBuf> {
+        let ctx_name = self
+            .ctx
+            .to_string()
+            .to_uppercase()
+            .replace('(', "_")
+            .replace(')', "")
+            .replace(':', "_");
+        let dump_path = dump_root.as_ref().join(format!("_tvmdbg_ctx_{}", ctx_name));
+        fs::create_dir_all(&dump_path)?;
+
+        let graph_dump = File::create(dump_path.join("_tvmdbg_graph_dump.json"))?;
+        serde_json::to_writer_pretty(BufWriter::new(graph_dump), &self.graph_dump())?;
+
+        let mut params = Vec::new();
+        for profile in &self.nodes {
+            for (index, output) in profile.outputs.iter().enumerate() {
+                params.push((format!("{}____{}", profile.name, index), output));
+            }
+        }
+        let mut output_tensors =
+            BufWriter::new(File::create(dump_path.join("output_tensors.params"))?);
+        ndarray::save_params(&params, &mut output_tensors)?;
+
+        let trace = File::create(dump_path.join("_tvmdbg_execution_trace.json"))?;
+        serde_json::to_writer(BufWriter::new(trace), &self.chrome_trace())?;
+        Ok(dump_path)
+    }
+
+    /// Returns the nodes with the names of their inputs, their operators, output types
+    /// and shapes, like `_update_graph_json` of the debug runtime of TVM.
+    fn graph_dump(&self) -> Value {
+        let graph = &self.graph;
+        let nodes: Vec<Value> = graph
+            .nodes
+            .iter()
+            .zip(&self.nodes)
+            .enumerate()
+            .map(|(node_id, (node, profile))| {
+                let entry_id = graph.entry_id(&NodeEntry::new(node_id, 0));
+                let mut attrs = node.attrs.clone().unwrap_or_default();
+                let dtype = graph
+                    .entry_attr("dltype", entry_id)
+                    .and_then(|dtype| dtype.as_str())
+                    .unwrap_or("");
+                attrs.insert("T".to_owned(), json!(format!("type: {}", dtype)));
+                let inputs: Vec<&str> = node
+                    .inputs
+                    .iter()
+                    .map(|entry| graph.nodes[entry.node_id].name.as_str())
+                    .collect();
+                json!({
+                    "op": profile.op,
+                    "name": node.name,
+                    "inputs": inputs,
+                    "attrs": attrs,
+                    "shape": graph.entry_attr("shape", entry_id),
+                })
+            })
+            .collect();
+        json!({ "nodes": nodes })
+    }
+
+    /// Returns the nodes as consecutive events in the Chrome trace format.
+    fn chrome_trace(&self) -> Value {
+        let mut events = Vec::with_capacity(2 * self.nodes.len());
+        let mut start = 0.0;
+        for profile in &self.nodes {
+            let end = start + profile.time;
+            for &(phase, time) in &[("B", start), ("E", end)] {
+                events.push(json!({
+                    "ph": phase,
+                    "ts": time * 1e6,
+                    "pid": 1,
+                    "tid": 1,
+                    "name": profile.name,
+                }));
+            }
+            start = end;
+        }
+        json!({ "displayTimeUnit": "ns", "traceEvents": events })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn dump() {
+        let graph = Graph::from_json(
+            r#"{
+            "nodes": [
+                {"op": "null", "name": "x", "inputs": []},
+                {"op": "tvm_op", "name": "relu", "attrs": {"func_name": "fuse_relu",
+                    "num_inputs": "1", "num_outputs": "1"}, "inputs": [[0, 0, 0]]}
+            ],
+            "arg_nodes": [0],
+            "heads": [[1, 0, 0]],
+            "attrs": {
+                "dltype": ["list_str", ["float32", "float32"]],
+                "shape": ["list_shape", [[1, 4], [1, 4]]]
+            }
+        }"#,
+        )
+        .unwrap();
+        let nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(node_id, node)| NodeProfile {
+                node_id,
+                name: node.name.clone(),
+                op: op_name(node).to_owned(),
+                time: 1e-6 * node_id as f64,
+                outputs: Vec::new(),
+            })
+            .collect();
+        let result = DebugResult {
+            nodes,
+            graph,
+            ctx: TVMContext::cpu(0),
+        };
+        assert_eq!(result.total_time(), 1e-6);
+        let table = result.table();
+        // the header, the separator and one row per operator
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Node Name  Ops        Time(us)"));
+        assert!(lines[2].starts_with("relu       fuse_relu  1.000     100.000  [1, 4]"));
+
+        let graph_dump = result.graph_dump();
+        assert_eq!(graph_dump["nodes"][1]["op"], json!("fuse_relu"));
+        assert_eq!(graph_dump["nodes"][1]["inputs"], json!(["x"]));
+        assert_eq!(graph_dump["nodes"][1]["attrs"]["T"], json!("type: float32"));
+        assert_eq!(graph_dump["nodes"][0]["shape"], json!([1, 4]));
+        let trace = result.chrome_trace();
+        assert_eq!(trace["traceEvents"][3]["ts"], json!(1.0));
+
+        let dump_root = env::temp_dir().join(format!("tvm-frontend-debug-dump-{}", process::id()));
+        let dump_path = result.dump(&dump_root).unwrap();
+        assert_eq!(dump_path, dump_root.join("_tvmdbg_ctx_CPU_0"));
+        assert!(dump_path.join("output_tensors.params").exists());
+        fs::remove_dir_all(&dump_root).unwrap();
+    }
+}
@@ -0,0 +1,365 @@
+//! Provides the parsed `graph.json` of a compiled model and the [`GraphRuntime`] running it.
+
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use serde_json::{self, Value};
+
+use function::Function;
+use internal_api;
+use ErrorKind;
+use Module;
+use NDArray;
+use Result;
+use TVMContext;
+
+/// Output `index` of the node `node_id`, which is an input of another node or an output
+/// of the graph.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "[usize; 3]", into = "[usize; 3]")]
+pub struct NodeEntry {
+    pub node_id: usize,
+    pub index: usize,
+    pub version: usize,
+}
+
+impl NodeEntry {
+    /// Creates the entry of output `index` of the node `node_id`.
+    pub fn new(node_id: usize, index: usize) -> Self {
+        NodeEntry {
+            node_id,
+            index,
+            version: 0,
+        }
+    }
+}
+
+impl From<[usize; 3]> for NodeEntry {
+    fn from(entry: [usize; 3]) -> Self {
+        NodeEntry {
+            node_id: entry[0],
+            index: entry[1],
+            version: entry[2],
+        }
+    }
+}
+
+impl From<NodeEntry> for [usize; 3] {
+    fn from(entry: NodeEntry) -> Self {
+        [entry.node_id, entry.index, entry.version]
+    }
+}
+
+/// Node of the graph, which is either an operator, or an input or a parameter with
+/// the op `null`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub op: String,
+    pub name: String,
+    pub inputs: Vec<NodeEntry>,
+    /// Attributes of an operator such as `func_name` and `num_outputs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attrs: Option<BTreeMap<String, Value>>,
+}
+
+impl Node {
+    /// Returns true if the node is an input or a parameter of the graph.
+    pub fn is_param(&self) -> bool {
+        self.op == "null"
+    }
+
+    /// Returns an attribute of the node, which is a string in `graph.json`.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .as_ref()
+            .and_then(|attrs| attrs.get(name))
+            .and_then(|value| value.as_str())
+    }
+
+    /// Returns the number of outputs of the node.
+    pub fn num_outputs(&self) -> usize {
+        self.attr("num_outputs")
+            .and_then(|num| num.parse().ok())
+            .unwrap_or(1)
+    }
+}
+
+/// Graph of a compiled model as stored in `graph.json`.
+///
+/// ## Example
+///
+/// ```
+/// let graph = Graph::from_json(&fs::read_to_string("graph.json").unwrap()).unwrap();
+/// for node in graph.nodes.iter().filter(|node| !node.is_param()) {
+///     println!("{}: {:?}", node.name, node.attr("func_name"));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub arg_nodes: Vec<usize>,
+    pub heads: Vec<NodeEntry>,
+    /// Index of the first output of each node in the data entries, and the number of
+    /// data entries at the end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_row_ptr: Option<Vec<usize>>,
+    /// Attributes of the data entries, such as `shape`, `dltype` and `storage_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attrs: Option<BTreeMap<String, Value>>,
+}
+
+impl Graph {
+    /// Parses a graph from the content of `graph.json`.
+    pub fn from_json(graph_json: &str) -> Result<Graph> {
+        let graph: Graph = serde_json::from_str(graph_json)?;
+        for entry in graph
+            .heads
+            .iter()
+            .chain(graph.nodes.iter().flat_map(|n| &n.inputs))
+        {
+            if entry.node_id >= graph.nodes.len() {
+                bail!(ErrorKind::InvalidFormat(format!(
+                    "graph entry of node {}, but there are {} nodes",
+                    entry.node_id,
+                    graph.nodes.len()
+                )));
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Serializes the graph in the format of `graph.json`.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Returns the index of the node named `name`.
+    pub fn node_index(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    /// Returns the index of an entry in the data entries, which index the entry attributes.
+    pub fn entry_id(&self, entry: &NodeEntry) -> usize {
+        match self.node_row_ptr {
+            Some(ref row_ptr) => row_ptr[entry.node_id] + entry.index,
+            None => {
+                self.nodes[..entry.node_id]
+                    .iter()
+                    .map(|node| node.num_outputs())
+                    .sum::<usize>()
+                    + entry.index
+            }
+        }
+    }
+
+    /// Returns a copy of the graph which outputs every output of the nodes `names` after
+    /// the outputs of the graph, along with the index of each of them in the outputs.
+    ///
+    /// The graph memory plan shares the storage of the intermediate outputs, which the
+    /// following operators would overwrite, so the new outputs are given storages of their own.
+    pub fn with_outputs(&self, names: &[&str]) -> Result<(Graph, Vec<(NodeEntry, usize)>)> {
+        let mut graph = self.clone();
+        let mut outputs = Vec::new();
+        for name in names {
+            let node_id = match self.node_index(name) {
+                Some(node_id) => node_id,
+                None => bail!(ErrorKind::UnknownNode(name.to_string())),
+            };
+            for index in 0..self.nodes[node_id].num_outputs() {
+                let entry = NodeEntry::new(node_id, index);
+                if let Some(head) = graph.heads.iter().position(|head| *head == entry) {
+                    outputs.push((entry, head));
+                    continue;
+                }
+                if !self.nodes[node_id].is_param() {
+                    let entry_id = self.entry_id(&entry);
+                    graph.new_storage(entry_id)?;
+                }
+                outputs.push((entry, graph.heads.len()));
+                graph.heads.push(entry);
+            }
+        }
+        Ok((graph, outputs))
+    }
+
+    /// Assigns an unused storage to the data entry `entry_id`.
+    fn new_storage(&mut self, entry_id: usize) -> Result<()> {
+        let storage_ids = match self
+            .attrs
+            .as_mut()
+            .and_then(|attrs| attrs.get_mut("storage_id"))
+            .and_then(|attr| attr.get_mut(1))
+            .and_then(|values| values.as_array_mut())
+        {
+            Some(storage_ids) => storage_ids,
+            // without a memory plan, the runtime does not share storages
+            None => return Ok(()),
+        };
+        let next_id = storage_ids
+            .iter()
+            .filter_map(|id| id.as_u64())
+            .max()
+            .map_or(0, |id| id + 1);
+        match storage_ids.get_mut(entry_id) {
+            Some(storage_id) => *storage_id = Value::from(next_id),
+            None => bail!(ErrorKind::InvalidFormat(format!(
+                "no storage of data entry {}",
+                entry_id
+            ))),
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the attribute `name` of the data entry `entry_id`. Attributes
+    /// are stored as `[type, values]`, e.g. `"shape": ["list_shape", [[1, 1000]]]`.
+    pub fn entry_attr(&self, name: &str, entry_id: usize) -> Option<&Value> {
+        self.attrs
+            .as_ref()
+            .and_then(|attrs| attrs.get(name))
+            .and_then(|attr| attr.get(1))
+            .and_then(|values| values.get(entry_id))
+    }
+}
+
+/// Runtime of a graph compiled by TVM, wrapping the module created by
+/// `tvm.graph_runtime.create`.
+///
+/// ## Example
+///
+/// ```
+/// let lib = Module::load(Path::new("deploy_lib.so")).unwrap();
+/// let graph_json = fs::read_to_string("deploy_graph.json").unwrap();
+/// let runtime = GraphRuntime::create(&graph_json, &lib, &TVMContext::cpu(0)).unwrap();
+/// runtime.load_params(&fs::read("deploy_param.params").unwrap()).unwrap();
+/// runtime.set_input("data", &input).unwrap();
+/// runtime.run().unwrap();
+/// let output = runtime.get_output(0).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphRuntime {
+    module: Module,
+}
+
+impl GraphRuntime {
+    /// Creates a runtime of the graph `graph_json` with the operators of `lib` on `ctx`.
+    pub fn create(graph_json: &str, lib: &Module, ctx: &TVMContext) -> Result<GraphRuntime> {
+        let module = create_runtime_module("tvm.graph_runtime.create", graph_json, lib, ctx)?;
+        Ok(GraphRuntime::from_module(module))
+    }
+
+    /// Wraps the module of a graph runtime.
+    pub fn from_module(module: Module) -> GraphRuntime {
+        GraphRuntime { module }
+    }
+
+    /// Returns the underlying runtime module.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    pub(crate) fn get_function(&self, name: &str) -> Result<Function> {
+        self.module.get_function(name, false)
+    }
+
+    /// Copies `input` to the input named `name`.
+    pub fn set_input(&self, name: &str, input: &NDArray) -> Result<()> {
+        let set_input = self.get_function("set_input")?;
+        call_packed!(set_input, name, input)?;
+        Ok(())
+    }
+
+    /// Loads the parameters saved by TVM, e.g. in `deploy_param.params`.
+    pub fn load_params(&self, params: &[u8]) -> Result<()> {
+        let load_params = self.get_function("load_params")?;
+        call_packed!(load_params, params)?;
+        Ok(())
+    }
+
+    /// Runs the graph.
+    pub fn run(&self) -> Result<()> {
+        let run = self.get_function("run")?;
+        run.call(&[])?;
+        Ok(())
+    }
+
+    /// Returns the number of outputs of the graph.
+    pub fn num_outputs(&self) -> Result<usize> {
+        let get_num_outputs = self.get_function("get_num_outputs")?;
+        Ok(get_num_outputs.call(&[])?.to_int() as usize)
+    }
+
+    /// Returns the output `index` of the graph, which shares the memory of the runtime
+    /// and is overwritten by the next run.
+    pub fn get_output(&self, index: usize) -> Result<NDArray> {
+        let get_output = self.get_function("get_output")?;
+        let ret = call_packed!(get_output, &index)?;
+        NDArray::try_from(ret)
+    }
+}
+
+/// Creates a graph runtime module with the global function `create`.
+pub(crate) fn create_runtime_module(
+    create: &str,
+    graph_json: &str,
+    lib: &Module,
+    ctx: &TVMContext,
+) -> Result<Module> {
+    let create = internal_api::try_get_api(create)?;
+    let device_type = usize::from(ctx.device_type);
+    let ret = call_packed!(create, graph_json, lib, &device_type, &ctx.device_id)?;
+    Module::try_from(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAPH_JSON: &str = r#"{
+        "nodes": [
+            {"op": "null", "name": "x", "inputs": []},
+            {"op": "tvm_op", "name": "relu", "attrs": {"func_name": "fuse_relu",
+                "num_inputs": "1", "num_outputs": "1", "flatten_data": "0"},
+                "inputs": [[0, 0, 0]]}
+        ],
+        "arg_nodes": [0],
+        "node_row_ptr": [0, 1, 2],
+        "heads": [[1, 0, 0]],
+        "attrs": {
+            "dltype": ["list_str", ["float32", "float32"]],
+            "shape": ["list_shape", [[1, 4], [1, 4]]],
+            "storage_id": ["list_int", [0, 1]]
+        }
+    }"#;
+
+    #[test]
+    fn parse_graph() {
+        let graph = Graph::from_json(GRAPH_JSON).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.nodes[0].is_param());
+        assert_eq!(graph.nodes[1].attr("func_name"), Some("fuse_relu"));
+        assert_eq!(graph.nodes[1].inputs, vec![NodeEntry::new(0, 0)]);
+        assert_eq!(graph.node_index("relu"), Some(1));
+        assert_eq!(graph.entry_id(&graph.heads[0]), 1);
+        assert_eq!(graph.entry_attr("dltype", 1), Some(&json!("float32")));
+        assert_eq!(graph.entry_attr("shape", 0), Some(&json!([1, 4])));
+
+        let mut without_row_ptr = graph.clone();
+        without_row_ptr.node_row_ptr = None;
+        assert_eq!(without_row_ptr.entry_id(&graph.heads[0]), 1);
+
+        let (with_outputs, outputs) = graph.with_outputs(&["x", "relu"]).unwrap();
+        assert_eq!(with_outputs.heads, vec![NodeEntry::new(1, 0), NodeEntry::new(0, 0)]);
+        assert_eq!(outputs, vec![(NodeEntry::new(0, 0), 1), (NodeEntry::new(1, 0), 0)]);
+        let (with_outputs, _) = without_row_ptr.with_outputs(&["x"]).unwrap();
+        assert_eq!(with_outputs.entry_attr("storage_id", 0), Some(&json!(0)));
+        let mut without_heads = graph.clone();
+        without_heads.heads.clear();
+        let (with_outputs, _) = without_heads.with_outputs(&["relu"]).unwrap();
+        assert_eq!(with_outputs.entry_attr("storage_id", 1), Some(&json!(2)));
+        assert!(graph.with_outputs(&["y"]).is_err());
+
+        let json = graph.to_json().unwrap();
+        assert!(json.contains(r#""heads":[[1,0,0]]"#));
+        assert_eq!(Graph::from_json(&json).unwrap(), graph);
+        assert!(Graph::from_json(&GRAPH_JSON.replace("[1, 0, 0]", "[2, 0, 0]")).is_err());
+    }
+}
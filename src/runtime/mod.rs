@@ -0,0 +1,11 @@
+//! This module provides the configuration of the TVM runtime and the runtimes of
+//! compiled graphs.
+
+mod activations;
+mod debug;
+mod graph;
+pub mod threading;
+
+pub use self::activations::ActivationRuntime;
+pub use self::debug::{DebugGraphRuntime, DebugResult, NodeProfile};
+pub use self::graph::{Graph, GraphRuntime, Node, NodeEntry};
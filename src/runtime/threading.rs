@@ -0,0 +1,85 @@
+//! This module configures the worker thread pool which runs the parallel cpu kernels.
+//!
+//! Instead of fixing the number of workers with `TVM_NUM_THREADS` before the process
+//! starts, the pool can be reconfigured at runtime, for example per model or to sweep
+//! thread counts in benchmarks.
+//!
+//! *Note:* TVM keeps one pool per launching thread, so the configuration applies to
+//! the kernels launched from the calling thread.
+//!
+//! # Example
+//!
+//! ```
+//! threading::config_threadpool(AffinityMode::Big, 4).unwrap();
+//! assert_eq!(threading::num_threads().unwrap(), 4);
+//! ```
+
+use std::convert::TryFrom;
+
+use internal_api;
+use ArgValue;
+use Result;
+
+/// Cores the workers are bound to on processors with big and little cores,
+/// such as ARM big.LITTLE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AffinityMode {
+    /// Binds the workers to the big, i.e. fast, cores.
+    Big,
+    /// Binds the workers to the little cores.
+    Little,
+}
+
+impl Default for AffinityMode {
+    fn default() -> Self {
+        AffinityMode::Big
+    }
+}
+
+impl From<AffinityMode> for i32 {
+    fn from(mode: AffinityMode) -> Self {
+        match mode {
+            AffinityMode::Big => 1,
+            AffinityMode::Little => -1,
+        }
+    }
+}
+
+/// Reconfigures the thread pool with `num_threads` workers bound to the cores of `mode`.
+/// If `num_threads` is 0, all the cores of `mode` are used.
+pub fn config_threadpool(mode: AffinityMode, num_threads: usize) -> Result<()> {
+    let func = internal_api::try_get_api("runtime.config_threadpool")?;
+    func.call(&[ArgValue::from(i32::from(mode)), ArgValue::from(num_threads)])?;
+    Ok(())
+}
+
+/// Returns the number of workers of the thread pool in effect, which can be less than the
+/// configured number if there are fewer cores. Requires a TVM registering `runtime.NumThreads`.
+pub fn num_threads() -> Result<usize> {
+    let func = internal_api::try_get_api("runtime.NumThreads")?;
+    let ret = func.call(&[])?;
+    Ok(i64::try_from(ret)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ErrorKind;
+
+    #[test]
+    fn threadpool() {
+        // older TVM versions do not register `runtime.NumThreads`
+        let default = match num_threads() {
+            Ok(default) => default,
+            Err(err) => match *err.kind() {
+                ErrorKind::MissingGlobalFunction(_) => return,
+                _ => panic!("{}", err),
+            },
+        };
+        assert!(default > 0);
+        config_threadpool(AffinityMode::Big, 1).unwrap();
+        assert_eq!(num_threads().unwrap(), 1);
+        config_threadpool(AffinityMode::default(), 0).unwrap();
+        assert_eq!(num_threads().unwrap(), default);
+    }
+}
@@ -11,6 +11,7 @@
 //! ```
 
 use std::{
+    convert::TryFrom,
     ffi::{CStr, CString},
     fmt::{self, Display, Formatter},
     ops::{Deref, DerefMut},
@@ -18,30 +19,39 @@ use std::{
 
 use ts;
 
+use extension;
 use function::Function;
 use module::Module;
 use ndarray::NDArray;
+use node::NodeRef;
+use Error;
+use ErrorKind;
+use Result;
 use TVMByteArray;
 use TVMContext;
 use TVMDeviceType;
 
 /// TVM Type codes
-#[repr(u32)]
+///
+/// Codes from `kExtBegin` (15) up to `kExtEnd` (128) are extension types,
+/// see [`extension`](../extension/index.html).
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TypeCode {
-    kDLInt = 0,
-    kDLUInt = 1,
-    kDLFloat = 2,
-    kHandle = 3,
-    kNull = 4,
-    kTVMType = 5,
-    kTVMContext = 6,
-    kArrayHandle = 7,
-    kNodeHandle = 8,
-    kModuleHandle = 9,
-    kFuncHandle = 10,
-    kStr = 11,
-    kBytes = 12,
+    kDLInt,
+    kDLUInt,
+    kDLFloat,
+    kHandle,
+    kNull,
+    kTVMType,
+    kTVMContext,
+    kArrayHandle,
+    kNodeHandle,
+    kModuleHandle,
+    kFuncHandle,
+    kStr,
+    kBytes,
+    kNDArrayContainer,
+    kExt(i32),
 }
 
 impl Default for TypeCode {
@@ -50,9 +60,33 @@ impl Default for TypeCode {
     }
 }
 
-impl<'a> Into<TypeCode> for i32 {
-    fn into(self) -> TypeCode {
-        match self {
+impl From<TypeCode> for i32 {
+    fn from(code: TypeCode) -> Self {
+        match code {
+            TypeCode::kDLInt => 0,
+            TypeCode::kDLUInt => 1,
+            TypeCode::kDLFloat => 2,
+            TypeCode::kHandle => 3,
+            TypeCode::kNull => 4,
+            TypeCode::kTVMType => 5,
+            TypeCode::kTVMContext => 6,
+            TypeCode::kArrayHandle => 7,
+            TypeCode::kNodeHandle => 8,
+            TypeCode::kModuleHandle => 9,
+            TypeCode::kFuncHandle => 10,
+            TypeCode::kStr => 11,
+            TypeCode::kBytes => 12,
+            TypeCode::kNDArrayContainer => 13,
+            TypeCode::kExt(code) => code,
+        }
+    }
+}
+
+impl TryFrom<i32> for TypeCode {
+    type Error = Error;
+
+    fn try_from(code: i32) -> Result<Self> {
+        let code = match code {
             0 => TypeCode::kDLInt,
             1 => TypeCode::kDLUInt,
             2 => TypeCode::kDLFloat,
@@ -66,32 +100,36 @@ impl<'a> Into<TypeCode> for i32 {
             10 => TypeCode::kFuncHandle,
             11 => TypeCode::kStr,
             12 => TypeCode::kBytes,
-            _ => unreachable!(),
-        }
+            13 => TypeCode::kNDArrayContainer,
+            code if (extension::EXT_BEGIN..extension::EXT_END).contains(&code) => {
+                TypeCode::kExt(code)
+            }
+            code => bail!(ErrorKind::UnknownTypeCode(code)),
+        };
+        Ok(code)
     }
 }
 
 impl Display for TypeCode {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                TypeCode::kDLInt => "int",
-                TypeCode::kDLUInt => "uint",
-                TypeCode::kDLFloat => "float",
-                TypeCode::kHandle => "handle",
-                TypeCode::kNull => "null",
-                TypeCode::kTVMType => "TVM type",
-                TypeCode::kTVMContext => "TVM context",
-                TypeCode::kArrayHandle => "Array handle",
-                TypeCode::kNodeHandle => "Node handle",
-                TypeCode::kModuleHandle => "Module handle",
-                TypeCode::kFuncHandle => "Function handle",
-                TypeCode::kStr => "string",
-                TypeCode::kBytes => "bytes",
-            }
-        )
+        let name = match self {
+            TypeCode::kDLInt => "int",
+            TypeCode::kDLUInt => "uint",
+            TypeCode::kDLFloat => "float",
+            TypeCode::kHandle => "handle",
+            TypeCode::kNull => "null",
+            TypeCode::kTVMType => "TVM type",
+            TypeCode::kTVMContext => "TVM context",
+            TypeCode::kArrayHandle => "Array handle",
+            TypeCode::kNodeHandle => "Node handle",
+            TypeCode::kModuleHandle => "Module handle",
+            TypeCode::kFuncHandle => "Function handle",
+            TypeCode::kStr => "string",
+            TypeCode::kBytes => "bytes",
+            TypeCode::kNDArrayContainer => "NDArray container",
+            TypeCode::kExt(code) => return write!(f, "extension type {}", code),
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -141,8 +179,15 @@ impl_prim_type!(Module, kModuleHandle);
 
 impl_prim_type!(NDArray, kArrayHandle);
 
+impl_prim_type!(NodeRef, kNodeHandle);
+
 impl_prim_type!([u8], kBytes);
-impl_prim_type!(TVMByteArray, kBytes);
+
+impl<'a, 'b> From<&'a TVMByteArray<'b>> for TypeCode {
+    fn from(_arg: &TVMByteArray) -> Self {
+        TypeCode::kBytes
+    }
+}
 
 /// See the [module-level documentation](../ty/index.html) for more details.
 ///
@@ -162,6 +207,11 @@ impl TVMType {
             },
         }
     }
+
+    /// Returns the number of bytes of a single element of this type.
+    pub(crate) fn itemsize(&self) -> usize {
+        (self.inner.bits as usize * self.inner.lanes as usize + 7) / 8
+    }
 }
 
 impl<'a> From<&'a str> for TVMType {
@@ -199,7 +249,25 @@ impl Display for TVMType {
                 bits: 64,
                 lanes: 1,
             } => write!(f, "handle"),
-            _ => write!(f, "unknown type"),
+            ts::TVMType {
+                code: 1,
+                bits: 1,
+                lanes: 1,
+            } => write!(f, "bool"),
+            ts::TVMType { code, bits, lanes } => {
+                let name = match code {
+                    0 => "int",
+                    1 => "uint",
+                    2 => "float",
+                    4 => "handle",
+                    _ => return write!(f, "unknown type"),
+                };
+                write!(f, "{}{}", name, bits)?;
+                if lanes > 1 {
+                    write!(f, "x{}", lanes)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -1,5 +1,9 @@
-//! This module implements [`TVMArgValue`] and [`TVMRetValue`] types and their conversions
-//! to other supported `TVMValues`. `TVMRetValue` is the owned version of `TVMArgValue`.
+//! This module implements the [`ArgValue`] enum, the owned Rust representation of every value
+//! that can cross the TVM packed function boundary, together with the raw [`TVMArgValue`]
+//! used when calling into TVM. [`RetValue`] is an alias of [`ArgValue`] used for return values.
+//!
+//! An [`ArgValue`] converts losslessly to and from a raw `(TVMValue, type_code)` pair
+//! through [`ArgValue::to_raw`] and [`ArgValue::from_raw`].
 //!
 //! # Examples
 //!
@@ -7,53 +11,48 @@
 //! let a = 42i8;
 //! let arg = TVMArgValue::from(&a);
 //! assert_eq!(arg.to_int() as i8, a);
-//! let ret = TVMRetValue::from(&a);
-//! assert_eq!(ret.to_int() as i8, a);
+//! let ret = RetValue::from(a);
+//! assert_eq!(i64::try_from(ret).unwrap(), 42);
 //! ```
 
 use std::{
-    any::Any,
-    ffi::{CStr, CString},
+    convert::TryFrom,
+    ffi::{CStr, CString, NulError},
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
     mem,
     ops::{Deref, DerefMut},
-    os::raw::{c_char, c_void},
+    os::raw::{c_char, c_int, c_void},
+    ptr,
 };
 
 use ts;
 
+use extension;
 use ty::TypeCode;
+use Error;
+use ErrorKind;
+use ExtType;
+use ExtValue;
 use Function;
 use Module;
 use NDArray;
+use NodeRef;
 use TVMByteArray;
 use TVMContext;
 use TVMDeviceType;
 use TVMType;
-
-#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
-pub(crate) enum ValueKind {
-    Int,
-    Float,
-    Handle,
-    Str,
-    Bytes,
-    Type,
-    Context,
-    Return,
-}
+use Result;
 
 /// Wrapper around the underlying `TVMValue`.
 #[derive(Clone)]
 pub struct TVMValue {
-    pub(crate) kind: ValueKind,
     pub(crate) inner: ts::TVMValue,
 }
 
 impl TVMValue {
-    pub(crate) fn new(kind: ValueKind, inner: ts::TVMValue) -> Self {
-        TVMValue { kind, inner }
+    pub(crate) fn new(inner: ts::TVMValue) -> Self {
+        TVMValue { inner }
     }
 
     pub fn to_int(&self) -> i64 {
@@ -62,13 +61,13 @@ impl TVMValue {
 }
 
 macro_rules! impl_prim_val {
-    ($type:ty, $kind:expr, $field:ident, $cast:ty) => {
+    ($type:ty, $field:ident, $cast:ty) => {
         impl<'a> From<&'a $type> for TVMValue {
             fn from(arg: &$type) -> Self {
                 let inner = ts::TVMValue {
                     $field: *arg as $cast,
                 };
-                Self::new($kind, inner)
+                Self::new(inner)
             }
         }
 
@@ -77,68 +76,24 @@ macro_rules! impl_prim_val {
                 let inner = ts::TVMValue {
                     $field: *arg as $cast,
                 };
-                Self::new($kind, inner)
+                Self::new(inner)
             }
         }
     };
 }
 
-impl_prim_val!(usize, ValueKind::Int, v_int64, i64);
-impl_prim_val!(i64, ValueKind::Int, v_int64, i64);
-impl_prim_val!(i32, ValueKind::Int, v_int64, i64);
-impl_prim_val!(i16, ValueKind::Int, v_int64, i64);
-impl_prim_val!(i8, ValueKind::Int, v_int64, i64);
-impl_prim_val!(u64, ValueKind::Int, v_int64, i64);
-impl_prim_val!(u32, ValueKind::Int, v_int64, i64);
-impl_prim_val!(u16, ValueKind::Int, v_int64, i64);
-impl_prim_val!(u8, ValueKind::Int, v_int64, i64);
-impl_prim_val!(bool, ValueKind::Int, v_int64, i64);
-impl_prim_val!(f64, ValueKind::Float, v_float64, f64);
-impl_prim_val!(f32, ValueKind::Float, v_float64, f64);
-
-impl<'a> From<&'a str> for TVMValue {
-    fn from(arg: &str) -> TVMValue {
-        let arg = CString::new(arg).unwrap();
-        let inner = ts::TVMValue {
-            v_str: arg.as_ptr() as *const c_char,
-        };
-        mem::forget(arg);
-        Self::new(ValueKind::Str, inner)
-    }
-}
-
-impl<'a> From<&'a String> for TVMValue {
-    fn from(arg: &String) -> TVMValue {
-        let arg = CString::new(arg.as_bytes()).unwrap();
-        let inner = ts::TVMValue {
-            v_str: arg.as_ptr() as *const c_char,
-        };
-        mem::forget(arg);
-        Self::new(ValueKind::Str, inner)
-    }
-}
-
-impl<'a> From<&'a CString> for TVMValue {
-    fn from(arg: &CString) -> TVMValue {
-        let arg = arg.to_owned();
-        let inner = ts::TVMValue {
-            v_str: arg.as_ptr() as *const c_char,
-        };
-        mem::forget(arg);
-        Self::new(ValueKind::Str, inner)
-    }
-}
-
-impl<'a> From<&'a [u8]> for TVMValue {
-    fn from(arg: &[u8]) -> TVMValue {
-        let arg = arg.to_owned();
-        let inner = ts::TVMValue {
-            v_handle: &arg as *const _ as *mut c_void,
-        };
-        mem::forget(arg);
-        Self::new(ValueKind::Handle, inner)
-    }
-}
+impl_prim_val!(usize, v_int64, i64);
+impl_prim_val!(i64, v_int64, i64);
+impl_prim_val!(i32, v_int64, i64);
+impl_prim_val!(i16, v_int64, i64);
+impl_prim_val!(i8, v_int64, i64);
+impl_prim_val!(u64, v_int64, i64);
+impl_prim_val!(u32, v_int64, i64);
+impl_prim_val!(u16, v_int64, i64);
+impl_prim_val!(u8, v_int64, i64);
+impl_prim_val!(bool, v_int64, i64);
+impl_prim_val!(f64, v_float64, f64);
+impl_prim_val!(f32, v_float64, f64);
 
 macro_rules! impl_tvm_val_from_handle {
     ($($ty:ty),+) => {
@@ -148,19 +103,19 @@ macro_rules! impl_tvm_val_from_handle {
                     let inner = ts::TVMValue {
                         v_handle: arg.handle as *mut _ as *mut c_void,
                     };
-                    Self::new(ValueKind::Handle, inner)
+                    Self::new(inner)
                 }
             }
         )+
     }
 }
 
-impl_tvm_val_from_handle!(Module, Function, NDArray);
+impl_tvm_val_from_handle!(Module, Function, NDArray, NodeRef);
 
 impl<'a> From<&'a TVMType> for TVMValue {
     fn from(ty: &TVMType) -> Self {
         let inner = ts::TVMValue { v_type: ty.inner };
-        Self::new(ValueKind::Type, inner)
+        Self::new(inner)
     }
 }
 
@@ -169,25 +124,25 @@ impl<'a> From<&'a TVMContext> for TVMValue {
         let inner = ts::TVMValue {
             v_ctx: ctx.clone().into(),
         };
-        Self::new(ValueKind::Context, inner)
+        Self::new(inner)
     }
 }
 
 impl<'a> From<&'a TVMDeviceType> for TVMValue {
     fn from(dev: &TVMDeviceType) -> Self {
         let inner = ts::TVMValue {
-            v_int64: dev.0 as i64,
+            v_int64: usize::from(*dev) as i64,
         };
-        Self::new(ValueKind::Int, inner)
+        Self::new(inner)
     }
 }
 
-impl<'a> From<&'a TVMByteArray> for TVMValue {
+impl<'a, 'b> From<&'a TVMByteArray<'b>> for TVMValue {
     fn from(barr: &TVMByteArray) -> Self {
         let inner = ts::TVMValue {
             v_handle: &barr.inner as *const ts::TVMByteArray as *mut c_void,
         };
-        Self::new(ValueKind::Bytes, inner)
+        Self::new(inner)
     }
 }
 
@@ -220,87 +175,609 @@ impl DerefMut for TVMValue {
 /// This type is needed for passing the supported values as arguments to [`call_packed!`]
 /// or [`function::Builder`]. Checkout the methods for conversions.
 ///
+/// Strings and byte arrays are marshalled into temporaries owned by the argument itself,
+/// so they are freed together with it. Handles such as [`NDArray`] are borrowed.
+///
 /// ## Example
 ///
 /// ```
 /// let ctx = TVMContext::gpu(0);
 /// let arg = TVMArgValue::from(&ctx);
-/// assert_eq!(arg.to_ctx(), ctx);
+/// assert_eq!(arg.to_ctx().unwrap(), ctx);
 /// ```
 ///
 /// [`function::Builder`]:../function/struct.Builder.html
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct TVMArgValue<'a> {
     pub value: TVMValue,
     pub type_code: TypeCode,
+    // temporaries the raw value points into.
+    storage: Option<ArgStorage>,
     _lifetime: PhantomData<&'a ()>,
 }
 
+#[derive(Debug)]
+enum ArgStorage {
+    Str(CString),
+    Bytes(Box<ts::TVMByteArray>),
+    // string with an interior nul byte, which fails the call made with it
+    Invalid(NulError),
+}
+
 impl<'a> TVMArgValue<'a> {
     pub fn new(value: TVMValue, type_code: TypeCode) -> Self {
         TVMArgValue {
             value: value,
             type_code: type_code,
+            storage: None,
+            _lifetime: PhantomData,
+        }
+    }
+
+    pub(crate) fn has_storage(&self) -> bool {
+        self.storage.is_some()
+    }
+
+    /// Returns the error of an argument which cannot be passed to TVM, i.e. of a string
+    /// with an interior nul byte.
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.storage {
+            Some(ArgStorage::Invalid(ref err)) => Err(err.clone().into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn with_storage(storage: ArgStorage, type_code: TypeCode) -> Self {
+        let inner = match storage {
+            ArgStorage::Str(ref s) => ts::TVMValue { v_str: s.as_ptr() },
+            ArgStorage::Bytes(ref barr) => ts::TVMValue {
+                v_handle: &**barr as *const ts::TVMByteArray as *mut c_void,
+            },
+            ArgStorage::Invalid(_) => ts::TVMValue {
+                v_handle: ptr::null_mut(),
+            },
+        };
+        TVMArgValue {
+            value: TVMValue::new(inner),
+            type_code,
+            storage: Some(storage),
             _lifetime: PhantomData,
         }
     }
 }
 
-/// Main way to create a TVMArgValue from suported Rust values.
-impl<'b, 'a: 'b, T: 'b + ?Sized> From<&'b T> for TVMArgValue<'a>
-where
-    TVMValue: From<&'b T>,
-    TypeCode: From<&'b T>,
-{
-    fn from(arg: &'b T) -> Self {
-        TVMArgValue::new(TVMValue::from(arg), TypeCode::from(arg))
+impl<'a> Clone for TVMArgValue<'a> {
+    fn clone(&self) -> Self {
+        match self.storage {
+            Some(ArgStorage::Str(ref s)) => {
+                TVMArgValue::with_storage(ArgStorage::Str(s.clone()), self.type_code)
+            }
+            Some(ArgStorage::Bytes(ref barr)) => {
+                TVMArgValue::with_storage(ArgStorage::Bytes(barr.clone()), self.type_code)
+            }
+            Some(ArgStorage::Invalid(ref err)) => {
+                TVMArgValue::with_storage(ArgStorage::Invalid(err.clone()), self.type_code)
+            }
+            None => TVMArgValue::new(self.value.clone(), self.type_code),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b TVMArgValue<'a>> for TVMArgValue<'a> {
+    fn from(arg: &TVMArgValue<'a>) -> Self {
+        arg.clone()
+    }
+}
+
+macro_rules! impl_arg_from_value {
+    ($($type:ty),+) => {
+        $(
+            impl<'a, 'b> From<&'b $type> for TVMArgValue<'a> {
+                fn from(arg: &$type) -> Self {
+                    TVMArgValue::new(TVMValue::from(arg), TypeCode::from(arg))
+                }
+            }
+        )+
+    };
+}
+
+impl_arg_from_value!(usize, i64, i32, i16, i8, u64, u32, u16, u8, f64, f32);
+impl_arg_from_value!(TVMType, TVMContext, TVMDeviceType);
+
+// handles are only borrowed, so the argument must not outlive them.
+macro_rules! impl_arg_from_handle {
+    ($($type:ty),+) => {
+        $(
+            impl<'a> From<&'a $type> for TVMArgValue<'a> {
+                fn from(arg: &'a $type) -> Self {
+                    TVMArgValue::new(TVMValue::from(arg), TypeCode::from(arg))
+                }
+            }
+        )+
+    };
+}
+
+impl_arg_from_handle!(Module, Function, NDArray, NodeRef);
+
+/// A string with an interior nul byte cannot be passed to TVM, and the call made with it
+/// returns the error.
+impl<'a, 'b> From<&'b str> for TVMArgValue<'a> {
+    fn from(arg: &str) -> Self {
+        match CString::new(arg) {
+            Ok(arg) => TVMArgValue::with_storage(ArgStorage::Str(arg), TypeCode::kStr),
+            Err(err) => TVMArgValue::with_storage(ArgStorage::Invalid(err), TypeCode::kNull),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b String> for TVMArgValue<'a> {
+    fn from(arg: &String) -> Self {
+        TVMArgValue::from(arg.as_str())
+    }
+}
+
+impl<'a, 'b> From<&'b CStr> for TVMArgValue<'a> {
+    fn from(arg: &CStr) -> Self {
+        TVMArgValue::with_storage(ArgStorage::Str(arg.to_owned()), TypeCode::kStr)
     }
 }
 
-/// TVMRetValue is an owned TVMArgValue.
+impl<'a, 'b> From<&'b CString> for TVMArgValue<'a> {
+    fn from(arg: &CString) -> Self {
+        TVMArgValue::from(arg.as_c_str())
+    }
+}
+
+impl<'a> From<&'a [u8]> for TVMArgValue<'a> {
+    fn from(arg: &'a [u8]) -> Self {
+        let barr = Box::new(ts::TVMByteArray {
+            data: arg.as_ptr() as *const c_char,
+            size: arg.len(),
+        });
+        TVMArgValue::with_storage(ArgStorage::Bytes(barr), TypeCode::kBytes)
+    }
+}
+
+impl<'a> From<&'a ExtValue> for TVMArgValue<'a> {
+    fn from(ext: &'a ExtValue) -> Self {
+        let inner = ts::TVMValue {
+            v_handle: ext.handle,
+        };
+        TVMArgValue::new(TVMValue::new(inner), TypeCode::kExt(ext.type_code()))
+    }
+}
+
+impl<'a, 'b> From<&'a TVMByteArray<'b>> for TVMArgValue<'a> {
+    fn from(barr: &'a TVMByteArray<'b>) -> Self {
+        TVMArgValue::new(TVMValue::from(barr), TypeCode::kBytes)
+    }
+}
+
+/// Owned Rust representation of a value passed to or returned from a TVM packed function.
+///
+/// Handles (`NDArray`, `Module` and `Function`) obtained through [`ArgValue::from_raw`] are
+/// owned and released when the value is dropped, unless they are moved out of it.
 ///
 /// ## Example
 ///
 /// ```
 /// let ctx = TVMContext::gpu(0);
-/// let arg = TVMRetValue::from(&ctx);
-/// assert_eq!(arg.to_ctx(), ctx);
+/// let ret = RetValue::from(&ctx);
+/// assert_eq!(ret.type_code(), TypeCode::kTVMContext);
+/// assert_eq!(TVMContext::try_from(ret).unwrap(), ctx);
 /// ```
-// TODO: WIP for unification of runtime and frontend
 #[derive(Debug)]
-pub struct TVMRetValue {
-    pub value: TVMValue,
-    box_value: Box<Any>,
-    pub type_code: TypeCode,
+pub enum ArgValue {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    NDArray(NDArray),
+    Module(Module),
+    Func(Function),
+    Node(NodeRef),
+    Ext(ExtValue),
+    Type(TVMType),
+    Context(TVMContext),
+    Null,
+    Handle(*mut c_void),
 }
 
-impl TVMRetValue {
-    pub(crate) fn new(value: TVMValue, type_code: TypeCode) -> Self {
-        Self {
-            value,
-            box_value: box (), // starting the unification
-            type_code,
+/// Value returned from a TVM packed function.
+pub type RetValue = ArgValue;
+
+/// Kept for the `fn(&[TVMArgValue]) -> Result<TVMRetValue>` signature of registered functions.
+pub type TVMRetValue = RetValue;
+
+impl ArgValue {
+    /// Returns the TVM type code of the value.
+    pub fn type_code(&self) -> TypeCode {
+        match *self {
+            ArgValue::Int(_) => TypeCode::kDLInt,
+            ArgValue::UInt(_) => TypeCode::kDLUInt,
+            ArgValue::Float(_) => TypeCode::kDLFloat,
+            ArgValue::Str(_) => TypeCode::kStr,
+            ArgValue::Bytes(_) => TypeCode::kBytes,
+            // arrays are always passed as `DLTensor` handles
+            ArgValue::NDArray(_) => TypeCode::kArrayHandle,
+            ArgValue::Module(_) => TypeCode::kModuleHandle,
+            ArgValue::Func(_) => TypeCode::kFuncHandle,
+            ArgValue::Node(_) => TypeCode::kNodeHandle,
+            ArgValue::Ext(ref ext) => TypeCode::kExt(ext.type_code()),
+            ArgValue::Type(_) => TypeCode::kTVMType,
+            ArgValue::Context(_) => TypeCode::kTVMContext,
+            ArgValue::Null => TypeCode::kNull,
+            ArgValue::Handle(_) => TypeCode::kHandle,
+        }
+    }
+
+    /// Converts a raw value and its type code into an owned `ArgValue`.
+    ///
+    /// Strings and byte arrays are copied. Following the TVM C API, NDArray container,
+    /// module, function and node handles are owned by the caller, so they are taken over and
+    /// freed when the result is dropped, whereas a plain `DLTensor` array handle is borrowed
+    /// and becomes an [`NDArray`] view.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be a valid TVM value of type `type_code`, and the caller must own
+    /// any handle it contains.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for a context of an unknown device.
+    pub unsafe fn from_raw(value: ts::TVMValue, type_code: TypeCode) -> Result<ArgValue> {
+        let arg = match type_code {
+            TypeCode::kDLInt => ArgValue::Int(value.v_int64),
+            TypeCode::kDLUInt => ArgValue::UInt(value.v_int64 as u64),
+            TypeCode::kDLFloat => ArgValue::Float(value.v_float64),
+            TypeCode::kStr => {
+                ArgValue::Str(CStr::from_ptr(value.v_str).to_string_lossy().into_owned())
+            }
+            TypeCode::kBytes => {
+                let barr = &*(value.v_handle as *const ts::TVMByteArray);
+                ArgValue::Bytes(TVMByteArray::from_raw(barr).into_vec())
+            }
+            TypeCode::kArrayHandle => {
+                ArgValue::NDArray(NDArray::new(value.v_handle as ts::TVMArrayHandle, true))
+            }
+            TypeCode::kNDArrayContainer => {
+                ArgValue::NDArray(NDArray::new(value.v_handle as ts::TVMArrayHandle, false))
+            }
+            TypeCode::kModuleHandle => ArgValue::Module(Module::new(value.v_handle, false, None)),
+            TypeCode::kFuncHandle => ArgValue::Func(Function::new(value.v_handle, false, false)),
+            TypeCode::kTVMType => ArgValue::Type(TVMType::from(value.v_type)),
+            TypeCode::kTVMContext => ArgValue::Context(TVMContext::try_from(value.v_ctx)?),
+            TypeCode::kNull => ArgValue::Null,
+            TypeCode::kHandle => ArgValue::Handle(value.v_handle),
+            TypeCode::kNodeHandle => ArgValue::Node(NodeRef::new(value.v_handle, false)),
+            TypeCode::kExt(code) => ArgValue::Ext(ExtValue::from_raw(value.v_handle, code, false)),
+        };
+        Ok(arg)
+    }
+
+    /// Converts to a raw [`TVMArgValue`] borrowing from `self`, which keeps any
+    /// temporary it points into alive.
+    pub fn to_raw(&self) -> Result<TVMArgValue<'_>> {
+        let inner = match *self {
+            ArgValue::Int(v) => ts::TVMValue { v_int64: v },
+            ArgValue::UInt(v) => ts::TVMValue { v_int64: v as i64 },
+            ArgValue::Float(v) => ts::TVMValue { v_float64: v },
+            ArgValue::Str(ref s) => {
+                let s = CString::new(s.as_bytes())?;
+                return Ok(TVMArgValue::with_storage(ArgStorage::Str(s), TypeCode::kStr));
+            }
+            ArgValue::Bytes(ref b) => return Ok(TVMArgValue::from(b.as_slice())),
+            ArgValue::NDArray(ref arr) => ts::TVMValue {
+                v_handle: arr.handle as *mut c_void,
+            },
+            ArgValue::Module(ref module) => ts::TVMValue {
+                v_handle: module.handle,
+            },
+            ArgValue::Func(ref func) => ts::TVMValue {
+                v_handle: func.handle,
+            },
+            ArgValue::Node(ref node) => ts::TVMValue {
+                v_handle: node.handle,
+            },
+            ArgValue::Ext(ref ext) => ts::TVMValue {
+                v_handle: ext.handle,
+            },
+            ArgValue::Type(ref ty) => ts::TVMValue { v_type: ty.inner },
+            ArgValue::Context(ref ctx) => ts::TVMValue {
+                v_ctx: ctx.clone().into(),
+            },
+            ArgValue::Null => ts::TVMValue {
+                v_handle: ptr::null_mut(),
+            },
+            ArgValue::Handle(h) => ts::TVMValue { v_handle: h },
+        };
+        Ok(TVMArgValue::new(TVMValue::new(inner), self.type_code()))
+    }
+
+    pub fn to_int(&self) -> i64 {
+        match *self {
+            ArgValue::Int(v) => v,
+            ArgValue::UInt(v) => v as i64,
+            ArgValue::Null => 0,
+            ref other => panic!("Requires i64 or NULL, but found {:?}", other.type_code()),
+        }
+    }
+
+    pub fn to_float(&self) -> f64 {
+        match *self {
+            ArgValue::Float(v) => v,
+            ref other => panic!("Requires f64, but found {:?}", other.type_code()),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match *self {
+            ArgValue::Str(ref s) => s.clone(),
+            ref other => panic!("Requires string, but found {:?}", other.type_code()),
+        }
+    }
+
+    pub fn to_bytearray(&self) -> TVMByteArray<'static> {
+        match *self {
+            ArgValue::Bytes(ref b) => TVMByteArray::from(b.clone()),
+            ref other => panic!("Requires byte array, but found {:?}", other.type_code()),
+        }
+    }
+
+    pub fn to_type(&self) -> TVMType {
+        match *self {
+            ArgValue::Type(ty) => ty,
+            ref other => panic!("Requires TVMType, but found {:?}", other.type_code()),
+        }
+    }
+
+    pub fn to_ctx(&self) -> TVMContext {
+        match *self {
+            ArgValue::Context(ref ctx) => ctx.clone(),
+            ref other => panic!("Requires TVMContext, but found {:?}", other.type_code()),
         }
     }
 }
 
-impl Clone for TVMRetValue {
-    fn clone(&self) -> TVMRetValue {
-        TVMRetValue {
-            value: self.value.clone(),
-            box_value: box (),
-            type_code: self.type_code,
+macro_rules! impl_arg_value_from {
+    ($variant:ident, $cast:ty; $($type:ty),+) => {
+        $(
+            impl From<$type> for ArgValue {
+                fn from(arg: $type) -> Self {
+                    ArgValue::$variant(arg as $cast)
+                }
+            }
+
+            impl<'a> From<&'a $type> for ArgValue {
+                fn from(arg: &$type) -> Self {
+                    ArgValue::$variant(*arg as $cast)
+                }
+            }
+        )+
+    };
+}
+
+impl_arg_value_from!(Int, i64; usize, i64, i32, i16, i8, u64, u32, u16, u8, bool);
+impl_arg_value_from!(Float, f64; f64, f32);
+
+impl<'a> From<&'a str> for ArgValue {
+    fn from(arg: &str) -> Self {
+        ArgValue::Str(arg.to_owned())
+    }
+}
+
+impl<'a> From<&'a String> for ArgValue {
+    fn from(arg: &String) -> Self {
+        ArgValue::Str(arg.clone())
+    }
+}
+
+impl From<String> for ArgValue {
+    fn from(arg: String) -> Self {
+        ArgValue::Str(arg)
+    }
+}
+
+impl<'a> From<&'a [u8]> for ArgValue {
+    fn from(arg: &[u8]) -> Self {
+        ArgValue::Bytes(arg.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for ArgValue {
+    fn from(arg: Vec<u8>) -> Self {
+        ArgValue::Bytes(arg)
+    }
+}
+
+impl<'a, 'b> From<&'a TVMByteArray<'b>> for ArgValue {
+    fn from(barr: &TVMByteArray) -> Self {
+        ArgValue::Bytes(barr.data())
+    }
+}
+
+impl<'a> From<TVMByteArray<'a>> for ArgValue {
+    fn from(barr: TVMByteArray) -> Self {
+        ArgValue::Bytes(barr.into_vec())
+    }
+}
+
+impl From<NDArray> for ArgValue {
+    fn from(arr: NDArray) -> Self {
+        ArgValue::NDArray(arr)
+    }
+}
+
+impl From<Module> for ArgValue {
+    fn from(module: Module) -> Self {
+        ArgValue::Module(module)
+    }
+}
+
+impl From<Function> for ArgValue {
+    fn from(func: Function) -> Self {
+        ArgValue::Func(func)
+    }
+}
+
+impl From<NodeRef> for ArgValue {
+    fn from(node: NodeRef) -> Self {
+        ArgValue::Node(node)
+    }
+}
+
+impl From<ExtValue> for ArgValue {
+    fn from(ext: ExtValue) -> Self {
+        ArgValue::Ext(ext)
+    }
+}
+
+impl From<TVMType> for ArgValue {
+    fn from(ty: TVMType) -> Self {
+        ArgValue::Type(ty)
+    }
+}
+
+impl<'a> From<&'a TVMType> for ArgValue {
+    fn from(ty: &TVMType) -> Self {
+        ArgValue::Type(*ty)
+    }
+}
+
+impl From<TVMContext> for ArgValue {
+    fn from(ctx: TVMContext) -> Self {
+        ArgValue::Context(ctx)
+    }
+}
+
+impl<'a> From<&'a TVMContext> for ArgValue {
+    fn from(ctx: &TVMContext) -> Self {
+        ArgValue::Context(ctx.clone())
+    }
+}
+
+impl From<TVMDeviceType> for ArgValue {
+    fn from(dev: TVMDeviceType) -> Self {
+        ArgValue::Int(usize::from(dev) as i64)
+    }
+}
+
+impl<'a> From<&'a TVMDeviceType> for ArgValue {
+    fn from(dev: &TVMDeviceType) -> Self {
+        ArgValue::Int(usize::from(*dev) as i64)
+    }
+}
+
+macro_rules! impl_try_from_arg_value {
+    ($($type:ty, $variant:ident, $expected:expr);+) => {
+        $(
+            impl TryFrom<ArgValue> for $type {
+                type Error = Error;
+
+                fn try_from(val: ArgValue) -> Result<$type> {
+                    match val {
+                        ArgValue::$variant(v) => Ok(v),
+                        other => bail!(ErrorKind::TypeMismatch(
+                            $expected.to_owned(),
+                            other.type_code().to_string()
+                        )),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl TryFrom<ArgValue> for i64 {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<i64> {
+        match val {
+            ArgValue::Int(v) => Ok(v),
+            ArgValue::UInt(v) if v <= i64::max_value() as u64 => Ok(v as i64),
+            ArgValue::UInt(v) => bail!(ErrorKind::ValueOutOfRange("i64".to_owned(), v.to_string())),
+            other => bail!(ErrorKind::TypeMismatch(
+                "int".to_owned(),
+                other.type_code().to_string()
+            )),
+        }
+    }
+}
+
+impl TryFrom<ArgValue> for u64 {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<u64> {
+        match val {
+            ArgValue::UInt(v) => Ok(v),
+            ArgValue::Int(v) if v >= 0 => Ok(v as u64),
+            ArgValue::Int(v) => bail!(ErrorKind::ValueOutOfRange("u64".to_owned(), v.to_string())),
+            other => bail!(ErrorKind::TypeMismatch(
+                "uint".to_owned(),
+                other.type_code().to_string()
+            )),
+        }
+    }
+}
+
+impl_try_from_arg_value!(
+    f64, Float, "float";
+    String, Str, "string";
+    Vec<u8>, Bytes, "bytes";
+    NDArray, NDArray, "Array handle";
+    Module, Module, "Module handle";
+    Function, Func, "Function handle";
+    NodeRef, Node, "Node handle";
+    ExtValue, Ext, "extension type";
+    TVMType, Type, "TVM type";
+    TVMContext, Context, "TVM context"
+);
+
+macro_rules! impl_try_from_int_arg_value {
+    ($($type:ty),+) => {
+        $(
+            impl TryFrom<ArgValue> for $type {
+                type Error = Error;
+
+                fn try_from(val: ArgValue) -> Result<$type> {
+                    let v = i64::try_from(val)?;
+                    match <$type>::try_from(v) {
+                        Ok(v) => Ok(v),
+                        Err(_) => bail!(ErrorKind::ValueOutOfRange(
+                            stringify!($type).to_owned(),
+                            v.to_string()
+                        )),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_try_from_int_arg_value!(i32, i16, i8, u32, u16, u8, usize);
+
+impl TryFrom<ArgValue> for bool {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<bool> {
+        match val {
+            ArgValue::Int(v) => Ok(v != 0),
+            ArgValue::UInt(v) => Ok(v != 0),
+            other => bail!(ErrorKind::TypeMismatch(
+                "bool".to_owned(),
+                other.type_code().to_string()
+            )),
         }
     }
 }
 
-impl<'b, T: 'b + ?Sized> From<&'b T> for TVMRetValue
-where
-    TVMValue: From<&'b T>,
-    TypeCode: From<&'b T>,
-{
-    fn from(arg: &'b T) -> Self {
-        TVMRetValue::new(TVMValue::from(arg), TypeCode::from(arg))
+impl TryFrom<ArgValue> for f32 {
+    type Error = Error;
+
+    fn try_from(val: ArgValue) -> Result<f32> {
+        Ok(f64::try_from(val)? as f32)
     }
 }
 
@@ -324,7 +801,9 @@ macro_rules! impl_to_methods {
             unsafe { self.value.inner.v_float64 }
         }
 
-        pub fn to_bytearray(&self) -> TVMByteArray {
+        /// Copies the byte array into an owned [`TVMByteArray`] since the
+        /// underlying buffer is only valid until the next call to TVM.
+        pub fn to_bytearray(&self) -> TVMByteArray<'static> {
             assert_eq!(
                 self.type_code,
                 TypeCode::kBytes,
@@ -334,10 +813,12 @@ macro_rules! impl_to_methods {
             unsafe {
                 let barr_ptr =
                     mem::transmute::<*mut c_void, *mut ts::TVMByteArray>(self.value.inner.v_handle);
-                TVMByteArray::new(*barr_ptr)
+                TVMByteArray::from_raw(&*barr_ptr)
             }
         }
 
+        /// Returns the module borrowed for the duration of the call. Use
+        /// [`to_owned_value`](#method.to_owned_value) to keep it.
         pub fn to_module(&self) -> Module {
             assert_eq!(
                 self.type_code,
@@ -346,7 +827,20 @@ macro_rules! impl_to_methods {
                 self.type_code
             );
             let module_handle = unsafe { self.value.inner.v_handle };
-            Module::new(module_handle, false, None)
+            Module::new(module_handle, true, None)
+        }
+
+        /// Returns the node borrowed for the duration of the call. Use
+        /// [`to_owned_value`](#method.to_owned_value) to keep it.
+        pub fn to_node(&self) -> NodeRef {
+            assert_eq!(
+                self.type_code,
+                TypeCode::kNodeHandle,
+                "Requires node handle, but found {:?}",
+                self.type_code
+            );
+            let node_handle = unsafe { self.value.inner.v_handle };
+            NodeRef::new(node_handle, true)
         }
 
         pub fn to_string(&self) -> String {
@@ -365,10 +859,11 @@ macro_rules! impl_to_methods {
             ret_str.to_string()
         }
 
+        /// Returns a view of the array borrowed for the duration of the call.
         pub fn to_ndarray(&self) -> NDArray {
-            assert_eq!(
-                self.type_code,
-                TypeCode::kArrayHandle,
+            assert!(
+                self.type_code == TypeCode::kArrayHandle
+                    || self.type_code == TypeCode::kNDArrayContainer,
                 "Requires Array handle, but found {:?}",
                 self.type_code
             );
@@ -388,7 +883,7 @@ macro_rules! impl_to_methods {
             TVMType::from(ty)
         }
 
-        pub fn to_ctx(&self) -> TVMContext {
+        pub fn to_ctx(&self) -> Result<TVMContext> {
             assert_eq!(
                 self.type_code,
                 TypeCode::kTVMContext,
@@ -396,7 +891,7 @@ macro_rules! impl_to_methods {
                 self.type_code
             );
             let ctx = unsafe { self.value.inner.v_ctx };
-            TVMContext::from(ctx)
+            TVMContext::try_from(ctx)
         }
     };
 
@@ -405,16 +900,84 @@ macro_rules! impl_to_methods {
             impl_to_methods!($ty);
         }
     };
+}
 
-    (owned $ty:ty) => {
-        impl $ty {
-            impl_to_methods!($ty);
+impl_to_methods!(refnc TVMArgValue<'a>);
+
+impl<'a> TVMArgValue<'a> {
+    /// Converts to an owned [`ArgValue`] which can be kept after the call returns.
+    ///
+    /// Module, function, node and NDArray container handles, as well as values of extension
+    /// types registered in C++, are retained with `TVMCbArgToReturn`, so the result holds its
+    /// own reference. A plain `DLTensor` array handle or a Rust extension value cannot be
+    /// retained and stays borrowed. Returns an error if TVM fails to retain the handle or
+    /// the value is a context of an unknown device.
+    pub fn to_owned_value(&self) -> Result<ArgValue> {
+        let mut value = self.value.inner;
+        match self.type_code {
+            TypeCode::kExt(code) if extension::is_registered(code) => {
+                let handle = unsafe { value.v_handle };
+                return Ok(ArgValue::Ext(ExtValue::from_raw(handle, code, true)));
+            }
+            TypeCode::kModuleHandle
+            | TypeCode::kFuncHandle
+            | TypeCode::kNodeHandle
+            | TypeCode::kNDArrayContainer
+            | TypeCode::kExt(_) => {
+                let status = unsafe {
+                    ts::TVMCbArgToReturn(&mut value as *mut _, i32::from(self.type_code) as c_int)
+                };
+                if status != 0 {
+                    bail!(ErrorKind::TVMError(::get_last_error().to_owned()));
+                }
+            }
+            _ => (),
+        }
+        unsafe { ArgValue::from_raw(value, self.type_code) }
+    }
+
+    /// Borrows the Rust extension value of type `T` for the duration of the call.
+    pub fn to_ext<T: ExtType>(&self) -> Result<&T> {
+        match self.type_code {
+            TypeCode::kExt(code) => unsafe { extension::downcast(self.value.inner.v_handle, code) },
+            other => bail!(ErrorKind::TypeMismatch(
+                format!("extension type {}", T::TYPE_CODE),
+                other.to_string()
+            )),
         }
     }
 }
 
-impl_to_methods!(refnc TVMArgValue<'a>);
-impl_to_methods!(owned TVMRetValue);
+
+/// Type whose fields are passed in order as the arguments of a packed function,
+/// usually implemented with `#[derive(ToArgs)]`.
+///
+/// ## Example
+///
+/// ```
+/// #[derive(ToArgs)]
+/// struct GraphRuntimeArgs {
+///     graph: String,
+///     lib: Module,
+///     device_type: TVMDeviceType,
+///     device_id: usize,
+/// }
+///
+/// let ret = function::Builder::from(runtime_create_fn).args_from(&args).invoke()?;
+/// ```
+pub trait ToArgs {
+    /// Returns the arguments borrowing from `self`.
+    fn to_args(&self) -> Vec<TVMArgValue<'_>>;
+}
+
+/// Type rebuilt in order from the arguments of a packed function, usually implemented
+/// with `#[derive(FromArgs)]` to unpack the arguments in a registered function.
+///
+/// Each field is converted from an owned [`ArgValue`] with `TryFrom`.
+pub trait FromArgs: Sized {
+    /// Rebuilds the value, failing if the number or the types of the arguments mismatch.
+    fn from_args(args: &[TVMArgValue]) -> Result<Self>;
+}
 
 #[cfg(test)]
 mod tests {
@@ -428,7 +991,7 @@ mod tests {
                     let v = $v as $ty;
                     let a = TVMArgValue::from(&v);
                     assert_eq!(a.to_int() as $ty, v);
-                    let b = TVMRetValue::from(&v);
+                    let b = RetValue::from(&v);
                     assert_eq!(b.to_int() as $ty, v);
                 )+
             }};
@@ -437,7 +1000,7 @@ mod tests {
                     let v = $v as $ty;
                     let a = TVMArgValue::from(&v);
                     assert_eq!(a.to_float() as $ty, v);
-                    let b = TVMRetValue::from(&v);
+                    let b = RetValue::from(&v);
                     assert_eq!(b.to_float() as $ty, v);
                 )+
             }};
@@ -451,40 +1014,123 @@ mod tests {
     fn bytearray() {
         let v = CString::new(b"hello".to_vec()).unwrap();
         let v = v.into_bytes();
-        let tvm = TVMRetValue::from(&v[..]);
-        assert_eq!(
-            tvm.to_bytearray().data(),
-            v.iter().map(|e| *e as i8).collect::<Vec<i8>>()
-        );
+        let tvm = RetValue::from(&v[..]);
+        assert_eq!(tvm.to_bytearray().data(), v);
         let w = vec![1u8, 2, 3, 4, 5];
-        let tvm = TVMRetValue::from(&w[..]);
-        assert_eq!(
-            tvm.to_bytearray().data(),
-            w.iter().map(|e| *e as i8).collect::<Vec<i8>>()
-        );
+        let tvm = RetValue::from(&w[..]);
+        assert_eq!(Vec::<u8>::try_from(tvm).unwrap(), w);
     }
 
     #[test]
     fn string() {
         let s = "hello";
-        let tvm_arg = TVMRetValue::from(s);
+        let tvm_arg = RetValue::from(s);
         assert_eq!(tvm_arg.to_string(), s.to_string());
         let s = "hello".to_string();
-        let tvm_arg = TVMRetValue::from(&s);
-        assert_eq!(tvm_arg.to_string(), s);
+        let tvm_arg = RetValue::from(&s);
+        assert_eq!(String::try_from(tvm_arg).unwrap(), s);
     }
 
     #[test]
     fn ty() {
         let t = TVMType::from("int");
-        let tvm = TVMRetValue::from(&t);
+        let tvm = RetValue::from(&t);
         assert_eq!(tvm.to_type(), t);
     }
 
     #[test]
     fn ctx() {
-        let c = TVMContext::from("gpu");
-        let tvm = TVMRetValue::from(&c);
+        let c = TVMContext::gpu(0);
+        let tvm = RetValue::from(&c);
         assert_eq!(tvm.to_ctx(), c);
     }
+
+    #[test]
+    fn raw_roundtrip() {
+        let values = vec![
+            ArgValue::from(-7i64),
+            ArgValue::UInt(u64::max_value()),
+            ArgValue::from(2.5f64),
+            ArgValue::from("hello"),
+            ArgValue::from(vec![0u8, 1, 2]),
+            ArgValue::from(TVMType::from("float")),
+            ArgValue::from(TVMContext::gpu(1)),
+            ArgValue::Null,
+        ];
+        for val in values {
+            let raw = val.to_raw().unwrap();
+            let raw = raw.clone();
+            assert_eq!(raw.type_code, val.type_code());
+            let back = unsafe { ArgValue::from_raw(raw.value.inner, raw.type_code) }.unwrap();
+            match (&val, &back) {
+                (ArgValue::Int(a), ArgValue::Int(b)) => assert_eq!(a, b),
+                (ArgValue::UInt(a), ArgValue::UInt(b)) => assert_eq!(a, b),
+                (ArgValue::Float(a), ArgValue::Float(b)) => assert_eq!(a, b),
+                (ArgValue::Str(a), ArgValue::Str(b)) => assert_eq!(a, b),
+                (ArgValue::Bytes(a), ArgValue::Bytes(b)) => assert_eq!(a, b),
+                (ArgValue::Type(a), ArgValue::Type(b)) => assert_eq!(a, b),
+                (ArgValue::Context(a), ArgValue::Context(b)) => assert_eq!(a, b),
+                (ArgValue::Null, ArgValue::Null) => {}
+                _ => panic!("{:?} became {:?}", val, back),
+            }
+        }
+    }
+
+    #[test]
+    fn ownership() {
+        let value = ts::TVMValue {
+            v_handle: ptr::null_mut(),
+        };
+        match unsafe { ArgValue::from_raw(value, TypeCode::kArrayHandle) }.unwrap() {
+            ArgValue::NDArray(arr) => assert!(arr.is_view()),
+            other => panic!("expected an array, found {:?}", other),
+        }
+        match unsafe { ArgValue::from_raw(value, TypeCode::kNDArrayContainer) }.unwrap() {
+            ArgValue::NDArray(arr) => {
+                assert!(!arr.is_view());
+                mem::forget(arr);
+            }
+            other => panic!("expected an array, found {:?}", other),
+        }
+        let arg = TVMArgValue::new(TVMValue::new(value), TypeCode::kModuleHandle);
+        assert!(arg.to_module().is_released());
+        assert!(arg.to_module().clone().is_released());
+    }
+
+    #[test]
+    fn nul_string() {
+        assert!(TVMArgValue::from("tvm").check().is_ok());
+        let arg = TVMArgValue::from("t\0vm");
+        assert!(arg.check().is_err());
+        assert!(arg.clone().check().is_err());
+    }
+
+    #[test]
+    fn narrow() {
+        assert_eq!(i32::try_from(ArgValue::from(-3i32)).unwrap(), -3);
+        assert_eq!(u8::try_from(ArgValue::UInt(255)).unwrap(), 255);
+        assert_eq!(
+            u8::try_from(ArgValue::from(256)).unwrap_err().to_string(),
+            "value `256` is out of the range of `u8`"
+        );
+        assert!(bool::try_from(ArgValue::from(true)).unwrap());
+        assert_eq!(f32::try_from(ArgValue::from(0.5f32)).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn uint() {
+        assert_eq!(u64::try_from(ArgValue::UInt(u64::max_value())).unwrap(), u64::max_value());
+        assert_eq!(i64::try_from(ArgValue::UInt(3)).unwrap(), 3);
+        assert!(i64::try_from(ArgValue::UInt(u64::max_value())).is_err());
+        assert!(u64::try_from(ArgValue::Int(-1)).is_err());
+    }
+
+    #[test]
+    fn mismatch() {
+        let err = f64::try_from(RetValue::from(1i32)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected type `float`, but found `int`".to_owned()
+        );
+    }
 }
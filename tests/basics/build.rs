@@ -1,17 +1,25 @@
 use std::process::Command;
 
-fn main() {
-    #[cfg(feature = "cpu")]
-    let script_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tvm_add_cpu.py");
-    #[cfg(feature = "gpu")]
-    let script_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tvm_add_gpu.py");
+fn run_script(name: &str) {
+    let script_path = format!("{}/src/{}", env!("CARGO_MANIFEST_DIR"), name);
     let output = Command::new("python")
-        .args(&[&script_path, env!("CARGO_MANIFEST_DIR")])
+        .arg(&script_path)
+        .arg(env!("CARGO_MANIFEST_DIR"))
         .output()
         .expect("Failed to execute command");
-    if output.stderr.len() > 0 {
-        panic!(String::from_utf8(output.stderr).unwrap());
+    // compilers may log warnings, e.g. autotvm about missing tuning configs
+    if !output.status.success() {
+        panic!("{}", String::from_utf8_lossy(&output.stderr));
     }
+}
+
+fn main() {
+    #[cfg(any(feature = "cpu", feature = "rpc"))]
+    run_script("tvm_add_cpu.py");
+    #[cfg(feature = "gpu")]
+    run_script("tvm_add_gpu.py");
+    #[cfg(any(feature = "cpu", feature = "debug"))]
+    run_script("tvm_graph_cpu.py");
     println!(
         "cargo:rustc-link-search=native={}",
         env!("CARGO_MANIFEST_DIR")
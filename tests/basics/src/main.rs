@@ -1,10 +1,51 @@
 extern crate ndarray as rust_ndarray;
 extern crate tvm_frontend as tvm;
 
-use std::path::Path;
+use std::{
+    env, fs,
+    path::Path,
+    process::{self, Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
 
 use tvm::*;
 
+/// Kills the RPC server when the test ends, also when it fails.
+struct ServerGuard(Child);
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Loads the graph, the library and the parameters built by `tvm_graph_cpu.py`, which
+/// computes `relu(x + w)` for an input `x` of shape `[1, 4]`.
+fn load_graph() -> (String, Module, Vec<u8>) {
+    let graph_json = fs::read_to_string("graph_cpu.json").unwrap();
+    let lib = Module::load(Path::new("graph_cpu.so")).unwrap();
+    let params = fs::read("graph_cpu.params").unwrap();
+    (graph_json, lib, params)
+}
+
+/// Connects to the RPC server, retrying until it listens or `timeout` passes.
+fn connect(host: &str, port: u16, timeout: Duration) -> rpc::Session {
+    let start = Instant::now();
+    loop {
+        match rpc::Session::connect(host, port, "") {
+            Ok(sess) => return sess,
+            Err(err) => {
+                if start.elapsed() > timeout {
+                    panic!("failed to connect to the RPC server: {}", err);
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
+
 fn main() {
     println!("start integration test");
     let shape = &mut [2];
@@ -29,6 +70,51 @@ fn main() {
             .unwrap();
 
         assert_eq!(ret.to_vec::<f32>().unwrap(), vec![6f32, 8.0]);
+
+        let evaluator = fadd
+            .time_evaluator("myadd", &TVMContext::cpu(0), 10, 3, 0)
+            .unwrap();
+        let args = [
+            TVMArgValue::from(&arr),
+            TVMArgValue::from(&arr),
+            TVMArgValue::from(&ret),
+        ];
+        let timing = evaluator.evaluate(&args).unwrap();
+        assert_eq!(timing.samples.len(), 3);
+        assert!(timing.samples.iter().all(|&sample| sample > 0.0));
+        assert!(timing.mean > 0.0 && timing.median > 0.0);
+        println!("success!")
+    }
+
+    if cfg!(feature = "cpu") {
+        println!("activation test");
+        let (graph_json, lib, params) = load_graph();
+        let graph = runtime::Graph::from_json(&graph_json).unwrap();
+        // `relu` is the head, `add` its input, and `w` a parameter and `x` an input,
+        // which have no storage of their own in the rewritten graph
+        let relu = &graph.nodes[graph.heads[0].node_id];
+        let add = graph.nodes[relu.inputs[0].node_id].name.clone();
+        let names = ["x", "w", add.as_str()];
+        let ctx = TVMContext::cpu(0);
+        let runtime = ActivationRuntime::create(&graph, &names, &lib, &ctx, &params).unwrap();
+        let mut input = empty(&mut [1, 4], ctx, TVMType::from("float"));
+        input.copy_from_buffer(&mut [1f32; 4]);
+        runtime.set_input("x", &input).unwrap();
+
+        let activations = runtime.activations().unwrap();
+        assert_eq!(runtime.names(), names);
+        let values: Vec<_> = activations
+            .iter()
+            .map(|(_, activation)| activation.to_vec::<f32>().unwrap())
+            .collect();
+        assert_eq!(values[0], vec![1f32; 4]);
+        assert_eq!(values[1], vec![1f32, -2.0, 3.0, -4.0]);
+        assert_eq!(values[2], vec![2f32, -1.0, 4.0, -3.0]);
+        // the output of the original graph is unchanged
+        assert_eq!(
+            runtime.get_output(0).unwrap().to_vec::<f32>().unwrap(),
+            vec![2f32, 0.0, 4.0, 0.0]
+        );
         println!("success!")
     }
 
@@ -56,4 +142,68 @@ fn main() {
         assert_eq!(ret.to_vec::<f32>().unwrap(), vec![6f32, 8.0]);
         println!("success!")
     }
+
+    if cfg!(feature = "rpc") {
+        println!("rpc test");
+        let _server = ServerGuard(
+            Command::new("python")
+                .args(&["-m", "tvm.exec.rpc_server", "--host", "127.0.0.1"])
+                .args(&["--port", "9091", "--port-end", "9092"])
+                .stdout(Stdio::null())
+                .spawn()
+                .expect("Failed to start the RPC server"),
+        );
+
+        let sess = connect("127.0.0.1", 9091, Duration::from_secs(30));
+        let lib = fs::read("add_cpu.so").unwrap();
+        sess.upload(&lib, "add_cpu.so").unwrap();
+        assert_eq!(sess.download("add_cpu.so").unwrap(), lib);
+        let mut fadd = sess.load_module("add_cpu.so").unwrap();
+        fadd.entry_func();
+
+        let ctx = sess.context(TVMDeviceType::Cpu, 0).unwrap();
+        let mut arr = empty(shape, ctx.clone(), TVMType::from("float"));
+        arr.copy_from_buffer(data.as_mut_slice());
+        let mut ret = empty(shape, ctx, TVMType::from("float"));
+        function::Builder::from(&mut fadd)
+            .arg(&arr)
+            .arg(&arr)
+            .set_output(&mut ret)
+            .invoke()
+            .unwrap();
+
+        assert_eq!(ret.to_vec::<f32>().unwrap(), vec![6f32, 8.0]);
+        sess.remove("add_cpu.so").unwrap();
+        println!("success!")
+    }
+
+    if cfg!(feature = "debug") {
+        println!("debug runtime test");
+        let (graph_json, lib, params) = load_graph();
+        let ctx = TVMContext::cpu(0);
+        let runtime = DebugGraphRuntime::create(&graph_json, &lib, &ctx).unwrap();
+        runtime.load_params(&params).unwrap();
+        let mut input = empty(&mut [1, 4], ctx.clone(), TVMType::from("float"));
+        input.copy_from_buffer(&mut [1f32; 4]);
+        runtime.set_input("x", &input).unwrap();
+
+        let result = runtime.profile(1, 1, 0).unwrap();
+        let graph = runtime.graph();
+        assert_eq!(result.nodes.len(), graph.nodes.len());
+        assert!(result.nodes.iter().all(|node| node.time >= 0.0));
+        // w = [1, -2, 3, -4]
+        let relu = &result.nodes[graph.heads[0].node_id];
+        assert_eq!(
+            relu.outputs[0].to_vec::<f32>().unwrap(),
+            vec![2f32, 0.0, 4.0, 0.0]
+        );
+        // the header, the separator and the rows of `add` and `relu`
+        assert_eq!(result.table().lines().count(), 4);
+
+        let dump_root = env::temp_dir().join(format!("tvm-basics-debug-{}", process::id()));
+        let dump_path = result.dump(&dump_root).unwrap();
+        assert!(dump_path.join("output_tensors.params").exists());
+        fs::remove_dir_all(&dump_root).unwrap();
+        println!("success!")
+    }
 }
@@ -0,0 +1,35 @@
+import os
+
+import numpy as np
+
+import nnvm
+import nnvm.compiler
+import tvm
+from tvm.contrib import cc
+
+
+def test_graph(target_dir):
+    """Builds relu(x + w) with the parameter w, keeping the operators unfused."""
+    x = nnvm.sym.Variable("x")
+    w = nnvm.sym.Variable("w")
+    y = nnvm.sym.elemwise_add(x, w, name="add")
+    y = nnvm.sym.relu(y, name="relu")
+    params = {"w": tvm.nd.array(np.array([[1, -2, 3, -4]], dtype="float32"))}
+    with nnvm.compiler.build_config(opt_level=0):
+        graph, lib, params = nnvm.compiler.build(
+            y, "llvm", shape={"x": (1, 4)}, params=params)
+
+    lib.save(os.path.join(target_dir, "graph_cpu.o"))
+    cc.create_shared(os.path.join(target_dir, "graph_cpu.so"),
+                     [os.path.join(target_dir, "graph_cpu.o")])
+    with open(os.path.join(target_dir, "graph_cpu.json"), "w") as fo:
+        fo.write(graph.json())
+    with open(os.path.join(target_dir, "graph_cpu.params"), "wb") as fo:
+        fo.write(nnvm.compiler.save_param_dict(params))
+
+
+if __name__ == "__main__":
+    import sys
+    if len(sys.argv) != 2:
+        sys.exit(-1)
+    test_graph(sys.argv[1])
@@ -1,4 +1,3 @@
-#![feature(extern_crate_item_prelude, try_from)]
 #![allow(unused_imports)]
 
 extern crate ndarray as rust_ndarray;
@@ -0,0 +1,39 @@
+#![allow(unused_imports)]
+
+#[macro_use]
+extern crate tvm_frontend;
+
+use tvm_frontend::*;
+
+#[derive(Debug, PartialEq, ToArgs, FromArgs)]
+struct Point {
+    x: i64,
+    y: f64,
+    label: String,
+}
+
+#[derive(ToArgs)]
+struct Pair(i64, String);
+
+fn main() {
+    register_global_func! {
+        fn describe(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+            let point = Point::from_args(args)?;
+            Ok(TVMRetValue::from(format!("{}: ({}, {})", point.label, point.x, point.y)))
+        }
+    }
+
+    let point = Point {
+        x: 1,
+        y: 2.5,
+        label: "p".to_owned(),
+    };
+    let mut registered = function::Builder::default();
+    registered.get_function("describe", true).args_from(&point);
+    assert_eq!(registered.arg_buf.len(), 3);
+    assert_eq!(registered.invoke().unwrap().to_string(), "p: (1, 2.5)");
+
+    let pair = Pair(1, "p".to_owned());
+    let err = registered.args_from(&pair).invoke().unwrap_err().to_string();
+    assert!(err.contains("`Point` expects 3 arguments, but found 2"));
+}
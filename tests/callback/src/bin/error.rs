@@ -1,7 +1,4 @@
-#![feature(extern_crate_item_prelude, panic_info_message)]
-#![allow(unused_imports, unused_must_use)]
-
-use std::panic;
+#![allow(unused_imports)]
 
 extern crate tvm_frontend as tvm;
 
@@ -23,20 +20,9 @@ fn main() {
     registered.args(&[10, 20]);
 
     println!("expected error message is:");
-    panic::set_hook(Box::new(|panic_info| {
-        if let Some(msg) = panic_info.message() {
-            println!("{:?}", msg);
-        }
-        if let Some(location) = panic_info.location() {
-            println!(
-                "panic occurred in file '{}' at line {}",
-                location.file(),
-                location.line()
-            );
-        } else {
-            println!("panic occurred but can't get location information");
-        }
-    }));
-
-    let _result = registered.invoke();
+    let err = registered.invoke().unwrap_err();
+    println!("{:?}", err.to_string());
+    assert!(err
+        .to_string()
+        .contains("expected type `i64`, but found `f64`"));
 }
@@ -0,0 +1,34 @@
+#![allow(unused_imports)]
+
+extern crate tvm_frontend as tvm;
+
+use tvm::*;
+
+struct Counter(i64);
+
+impl ExtType for Counter {
+    const TYPE_CODE: i32 = extension::EXT_RESERVE_END;
+}
+
+fn main() {
+    extension::register::<Counter>().unwrap();
+
+    fn count(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+        let mut ret = 0;
+        for arg in args.iter() {
+            ret += arg.to_ext::<Counter>()?.0;
+        }
+        Ok(TVMRetValue::from(ret))
+    }
+    tvm::function::register(count, "count".to_owned(), false).unwrap();
+
+    let first = ExtValue::new(Counter(10)).unwrap();
+    let second = ExtValue::new(Counter(20)).unwrap();
+    let mut registered = function::Builder::default();
+    registered
+        .get_function("count", true)
+        .arg(&first)
+        .arg(&second);
+    assert_eq!(registered.invoke().unwrap().to_int(), 30);
+    assert_eq!(first.into_inner::<Counter>().unwrap().0, 10);
+}
@@ -1,4 +1,3 @@
-#![feature(extern_crate_item_prelude)]
 #![allow(unused_imports)]
 
 #[macro_use]
@@ -1,4 +1,3 @@
-#![feature(extern_crate_item_prelude)]
 #![allow(unused_imports)]
 
 extern crate tvm_frontend as tvm;
@@ -0,0 +1,56 @@
+#![allow(unused_imports)]
+
+extern crate callback;
+#[macro_use]
+extern crate tvm_frontend as tvm;
+
+use callback::heap_in_use;
+use tvm::ty::TypeCode;
+use tvm::*;
+
+const CALLS: usize = 10_000;
+
+fn main() {
+    register_global_func! {
+        fn concat(args: &[TVMArgValue]) -> Result<TVMRetValue> {
+            let mut ret = "".to_owned();
+            for arg in args.iter() {
+                match arg.type_code {
+                    TypeCode::kStr => ret += arg.to_string().as_str(),
+                    _ => ret += &String::from_utf8(arg.to_bytearray().data()).unwrap(),
+                }
+            }
+            Ok(TVMRetValue::from(ret))
+        }
+    }
+
+    let text = "a".repeat(64);
+    let owned = "b".repeat(64);
+    let bytes = vec![b'c'; 256];
+    let barr = TVMByteArray::from(&bytes);
+    let expected = format!("{}{}{}{}", text, owned, "c".repeat(256), "c".repeat(256));
+    let call = || {
+        let mut registered = function::Builder::default();
+        registered
+            .get_function("concat", true)
+            .arg(text.as_str())
+            .arg(&owned)
+            .arg(&bytes[..])
+            .arg(&barr);
+        registered.invoke().unwrap().to_string()
+    };
+
+    // warm up the lazily initialized global function registry
+    assert_eq!(call(), expected);
+    let before = heap_in_use();
+    for _ in 0..CALLS {
+        assert_eq!(call(), expected);
+    }
+    let after = heap_in_use();
+    assert!(
+        after.saturating_sub(before) < CALLS,
+        "{} bytes leaked over {} calls",
+        after - before,
+        CALLS
+    );
+}
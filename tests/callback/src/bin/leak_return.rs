@@ -0,0 +1,45 @@
+#![allow(unused_imports)]
+
+extern crate callback;
+#[macro_use]
+extern crate tvm_frontend as tvm;
+
+use std::convert::TryFrom;
+
+use callback::heap_in_use;
+use tvm::*;
+
+const CALLS: usize = 1_000;
+
+// entries of the array returned by every call
+const LEN: usize = 1024;
+
+fn main() {
+    register_global_func! {
+        fn zeros(_args: &[TVMArgValue]) -> Result<TVMRetValue> {
+            let array = empty(&mut [LEN], TVMContext::cpu(0), TVMType::from("float"));
+            Ok(TVMRetValue::from(array))
+        }
+    }
+
+    let zeros = Function::get_function("zeros", true).unwrap();
+    let call = || {
+        let array = NDArray::try_from(zeros.call(&[]).unwrap()).unwrap();
+        assert_eq!(array.size().unwrap(), LEN);
+    };
+
+    // warm up the lazily initialized global function registry
+    call();
+    let before = heap_in_use();
+    for _ in 0..CALLS {
+        call();
+    }
+    let after = heap_in_use();
+    // a leaked array would hold at least `4 * LEN` bytes
+    assert!(
+        after.saturating_sub(before) < 4 * LEN,
+        "{} bytes leaked over {} calls",
+        after - before,
+        CALLS
+    );
+}
@@ -0,0 +1,68 @@
+#![allow(unused_imports)]
+
+extern crate tvm_frontend;
+
+use std::convert::TryFrom;
+
+use tvm_frontend::*;
+
+#[tvm_frontend::register_func("test.add")]
+fn add(a: i64, b: f64) -> Result<f64> {
+    Ok(a as f64 + b)
+}
+
+#[tvm_frontend::register_func("test.greet", override = true)]
+fn greet(name: String) -> String {
+    format!("hello {}", name)
+}
+
+#[tvm_frontend::register_func("test.shout")]
+fn shout(name: &str) -> String {
+    name.to_uppercase()
+}
+
+#[tvm_frontend::register_func("test.scale")]
+fn scale(a: i32, b: f32, negate: bool) -> f64 {
+    let product = f64::from(a) * f64::from(b);
+    if negate {
+        -product
+    } else {
+        product
+    }
+}
+
+#[tvm_frontend::register_func("test.nothing")]
+fn nothing() {}
+
+fn main() {
+    let add = Function::get_function("test.add", true).unwrap();
+    let ret = add.call(&[ArgValue::from(1), ArgValue::from(2.5)]).unwrap();
+    assert_eq!(f64::try_from(ret).unwrap(), 3.5);
+
+    let greet = Function::get_function("test.greet", true).unwrap();
+    let ret = call_packed!(greet, "tvm").unwrap();
+    assert_eq!(ret.to_string(), "hello tvm");
+
+    let shout = Function::get_function("test.shout", true).unwrap();
+    let ret = call_packed!(shout, "tvm").unwrap();
+    assert_eq!(ret.to_string(), "TVM");
+
+    let scale = Function::get_function("test.scale", true).unwrap();
+    let ret = scale
+        .call(&[ArgValue::from(3), ArgValue::from(0.5), ArgValue::from(1)])
+        .unwrap();
+    assert_eq!(f64::try_from(ret).unwrap(), -1.5);
+
+    let nothing = Function::get_function("test.nothing", true).unwrap();
+    assert_eq!(nothing.call(&[]).unwrap().to_int(), 0);
+
+    let err = add.call(&[ArgValue::from(1)]).unwrap_err().to_string();
+    assert!(err.contains("`test.add` expects 2 arguments, but found 1"));
+    let err = add
+        .call(&[ArgValue::from(1), ArgValue::from("2")])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(
+        "invalid argument `b` of `test.add`: expected type `float`, but found `string`"
+    ));
+}
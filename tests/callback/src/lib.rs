@@ -0,0 +1,30 @@
+//! Helpers shared by the callback tests.
+
+use std::os::raw::c_int;
+
+// `tvm_frontend` is a dylib with its own allocator, so heap usage is read from glibc
+// which also serves the allocations of the TVM runtime.
+#[allow(dead_code)]
+#[repr(C)]
+struct MallInfo {
+    arena: c_int,
+    ordblks: c_int,
+    smblks: c_int,
+    hblks: c_int,
+    hblkhd: c_int,
+    usmblks: c_int,
+    fsmblks: c_int,
+    uordblks: c_int,
+    fordblks: c_int,
+    keepcost: c_int,
+}
+
+extern "C" {
+    fn mallinfo() -> MallInfo;
+}
+
+/// Returns the number of bytes allocated on the heap.
+pub fn heap_in_use() -> usize {
+    let info = unsafe { mallinfo() };
+    info.uordblks as usize + info.hblkhd as usize
+}
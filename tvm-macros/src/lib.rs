@@ -0,0 +1,406 @@
+//! Procedural macros of the TVM Rust frontend, re-exported by `tvm_frontend`.
+//!
+//! The generated code refers to the frontend as `::tvm_frontend`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    Data, DeriveInput, Fields, FnArg, GenericArgument, Ident, ItemFn, LitBool, LitStr, Member, Pat,
+    PathArguments, ReturnType, Type,
+};
+
+/// Arguments of `#[register_func("name", override = true)]`.
+struct RegisterArgs {
+    name: LitStr,
+    override_: bool,
+}
+
+impl Parse for RegisterArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let mut override_ = false;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            // `override` is a reserved keyword
+            let key = input.call(Ident::parse_any)?;
+            if key != "override" {
+                return Err(syn::Error::new(key.span(), "expected `override = <bool>`"));
+            }
+            input.parse::<Token![=]>()?;
+            override_ = input.parse::<LitBool>()?.value;
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(RegisterArgs { name, override_ })
+    }
+}
+
+/// Kind of the return type of a registered function.
+enum Output {
+    Unit,
+    Value,
+    ResultUnit,
+    ResultValue,
+}
+
+fn is_unit(ty: &Type) -> bool {
+    match *ty {
+        Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+        _ => false,
+    }
+}
+
+fn output_kind(output: &ReturnType) -> Output {
+    let ty = match *output {
+        ReturnType::Default => return Output::Unit,
+        ReturnType::Type(_, ref ty) => ty,
+    };
+    if is_unit(ty) {
+        return Output::Unit;
+    }
+    if let Type::Path(ref path) = **ty {
+        if let Some(last) = path.path.segments.iter().last() {
+            if last.ident == "Result" {
+                if let PathArguments::AngleBracketed(ref generics) = last.arguments {
+                    match generics.args.iter().next() {
+                        Some(GenericArgument::Type(ref ty)) if is_unit(ty) => {
+                            return Output::ResultUnit
+                        }
+                        _ => return Output::ResultValue,
+                    }
+                }
+            }
+        }
+    }
+    Output::Value
+}
+
+/// Returns the owned type an argument of type `ty` is converted to, and whether the
+/// function borrows it, e.g. `String` for `&str`.
+fn owned_arg_type(ty: &Type) -> syn::Result<(Type, bool)> {
+    let reference = match *ty {
+        Type::Reference(ref reference) => reference,
+        _ => return Ok((ty.clone(), false)),
+    };
+    if let Some(mutability) = reference.mutability {
+        return Err(syn::Error::new(
+            mutability.span,
+            "arguments of a registered function cannot be mutable references",
+        ));
+    }
+    let owned = match *reference.elem {
+        Type::Path(ref path)
+            if path.qself.is_none()
+                && path.path.segments.len() == 1
+                && path.path.segments[0].ident == "str" =>
+        {
+            parse_quote!(String)
+        }
+        Type::Slice(ref slice) => {
+            let elem = &slice.elem;
+            parse_quote!(Vec<#elem>)
+        }
+        ref elem => elem.clone(),
+    };
+    Ok((owned, true))
+}
+
+/// Registers a Rust function with typed arguments as a global TVM packed function
+/// when the program starts.
+///
+/// The arguments are converted with `TryFrom<ArgValue>` and the return value, optionally
+/// wrapped in a `Result`, with `From` into a `RetValue`. Borrowed arguments such as `&str`
+/// and `&[u8]` are converted to their owned type first. Calls with a wrong number or
+/// wrong types of arguments fail with a descriptive error.
+///
+/// The registration runs from the initializer section of the binary, which is supported on
+/// Linux, Android, FreeBSD, macOS, iOS and Windows, and fails to compile on other targets.
+/// The process aborts if the function cannot be registered.
+///
+/// ## Example
+///
+/// ```
+/// #[tvm::register_func("test.add", override = true)]
+/// fn add(a: i64, b: f64) -> Result<f64> {
+///     Ok(a as f64 + b)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn register_func(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as RegisterArgs);
+    let func = parse_macro_input!(item as ItemFn);
+    match expand_register_func(args, func) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_register_func(args: RegisterArgs, func: ItemFn) -> syn::Result<TokenStream2> {
+    if !func.decl.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            func.ident.span(),
+            "a registered function cannot be generic",
+        ));
+    }
+    let name = &args.name;
+    let override_ = args.override_;
+    let ident = &func.ident;
+    let mut arg_names = Vec::new();
+    let mut arg_types = Vec::new();
+    for input in &func.decl.inputs {
+        match *input {
+            FnArg::Captured(ref arg) => match arg.pat {
+                Pat::Ident(ref pat) => {
+                    arg_names.push(pat.ident.clone());
+                    arg_types.push(arg.ty.clone());
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "arguments of a registered function must be identifiers",
+                    ))
+                }
+            },
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "a registered function cannot take `self`",
+                ))
+            }
+        }
+    }
+    let num_args = arg_names.len();
+    let mut call_args = Vec::with_capacity(num_args);
+    let mut owned_types = Vec::with_capacity(num_args);
+    for (arg, ty) in arg_names.iter().zip(&arg_types) {
+        let (owned, borrowed) = owned_arg_type(ty)?;
+        call_args.push(if borrowed { quote!(&#arg) } else { quote!(#arg) });
+        owned_types.push(owned);
+    }
+    let unpack_args: Vec<_> = arg_names
+        .iter()
+        .zip(&owned_types)
+        .enumerate()
+        .map(|(i, (arg, ty))| {
+            let arg_str = arg.to_string();
+            quote! {
+                let #arg: #ty = args[#i]
+                    .to_owned_value()
+                    .map_err(|err| err.to_string())
+                    .and_then(|arg| {
+                        <#ty as ::std::convert::TryFrom<::tvm_frontend::ArgValue>>::try_from(arg)
+                            .map_err(|err| err.to_string())
+                    })
+                    .map_err(|reason| {
+                        ::tvm_frontend::Error::from(::tvm_frontend::ErrorKind::InvalidArgument(
+                            #name.to_owned(),
+                            #arg_str.to_owned(),
+                            reason,
+                        ))
+                    })?;
+            }
+        })
+        .collect();
+    let call = quote!(#ident(#(#call_args),*));
+    let ret = match output_kind(&func.decl.output) {
+        Output::Unit => quote! {
+            #call;
+            Ok(::tvm_frontend::RetValue::Null)
+        },
+        Output::Value => quote!(Ok(::tvm_frontend::RetValue::from(#call))),
+        Output::ResultUnit => quote! {
+            #call?;
+            Ok(::tvm_frontend::RetValue::Null)
+        },
+        Output::ResultValue => quote!(Ok(::tvm_frontend::RetValue::from(#call?))),
+    };
+    let packed = Ident::new(&format!("__tvm_packed_{}", ident), Span::call_site());
+    let register = Ident::new(
+        &format!("__TVM_REGISTER_{}", ident.to_string().to_uppercase()),
+        Span::call_site(),
+    );
+
+    Ok(quote! {
+        #func
+
+        #[doc(hidden)]
+        fn #packed(
+            args: &[::tvm_frontend::TVMArgValue],
+        ) -> ::tvm_frontend::Result<::tvm_frontend::RetValue> {
+            if args.len() != #num_args {
+                return Err(::tvm_frontend::ErrorKind::ArgumentCountMismatch(
+                    #name.to_owned(),
+                    #num_args,
+                    args.len(),
+                ).into());
+            }
+            #(#unpack_args)*
+            #ret
+        }
+
+        // runs before `main` like a C++ static initializer
+        #[used]
+        #[doc(hidden)]
+        #[cfg_attr(
+            any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+            link_section = ".init_array"
+        )]
+        #[cfg_attr(
+            any(target_os = "macos", target_os = "ios"),
+            link_section = "__DATA,__mod_init_func"
+        )]
+        #[cfg_attr(windows, link_section = ".CRT$XCU")]
+        static #register: extern "C" fn() = {
+            // unwinding out of an initializer is undefined behavior, so abort instead
+            extern "C" fn register() {
+                let registered =
+                    ::tvm_frontend::function::register(#packed, #name.to_owned(), #override_);
+                if let Err(err) = registered {
+                    eprintln!("cannot register `{}`: {}", #name, err);
+                    ::std::process::abort();
+                }
+            }
+            register
+        };
+
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "macos",
+            target_os = "ios",
+            windows
+        )))]
+        compile_error!(concat!(
+            "cannot register `",
+            #name,
+            "` when the program starts on this target, use `register_global_func!` instead"
+        ));
+    })
+}
+
+/// Returns the members and types of the fields of a struct in order.
+fn struct_fields(input: &DeriveInput) -> syn::Result<Vec<(Member, Type)>> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "packed arguments can only be derived for structs",
+            ))
+        }
+    };
+    let members = match *fields {
+        Fields::Named(ref fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.clone().expect("named field");
+                (Member::Named(ident), field.ty.clone())
+            })
+            .collect(),
+        Fields::Unnamed(ref fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, field)| (Member::Unnamed(i.into()), field.ty.clone()))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    Ok(members)
+}
+
+fn member_name(member: &Member) -> String {
+    match *member {
+        Member::Named(ref ident) => ident.to_string(),
+        Member::Unnamed(ref index) => index.index.to_string(),
+    }
+}
+
+/// Derives `ToArgs` which passes the fields of a struct in order as packed function arguments.
+#[proc_macro_derive(ToArgs)]
+pub fn derive_to_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_to_args(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_to_args(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let members: Vec<_> = struct_fields(input)?
+        .into_iter()
+        .map(|(member, _)| member)
+        .collect();
+    Ok(quote! {
+        impl #impl_generics ::tvm_frontend::ToArgs for #ident #ty_generics #where_clause {
+            fn to_args(&self) -> Vec<::tvm_frontend::TVMArgValue<'_>> {
+                vec![#(::tvm_frontend::TVMArgValue::from(&self.#members)),*]
+            }
+        }
+    })
+}
+
+/// Derives `FromArgs` which rebuilds a struct from packed function arguments in order.
+#[proc_macro_derive(FromArgs)]
+pub fn derive_from_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_from_args(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_from_args(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = struct_fields(input)?;
+    let num_fields = fields.len();
+    let unpack_fields = fields.iter().enumerate().map(|(i, (member, ty))| {
+        let field = member_name(member);
+        quote! {
+            #member: args[#i]
+                .to_owned_value()
+                .map_err(|err| err.to_string())
+                .and_then(|arg| {
+                    <#ty as ::std::convert::TryFrom<::tvm_frontend::ArgValue>>::try_from(arg)
+                        .map_err(|err| err.to_string())
+                })
+                .map_err(|reason| {
+                    ::tvm_frontend::Error::from(::tvm_frontend::ErrorKind::InvalidArgument(
+                        #name.to_owned(),
+                        #field.to_owned(),
+                        reason,
+                    ))
+                })?
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::tvm_frontend::FromArgs for #ident #ty_generics #where_clause {
+            fn from_args(
+                args: &[::tvm_frontend::TVMArgValue],
+            ) -> ::tvm_frontend::Result<Self> {
+                if args.len() != #num_fields {
+                    return Err(::tvm_frontend::ErrorKind::ArgumentCountMismatch(
+                        #name.to_owned(),
+                        #num_fields,
+                        args.len(),
+                    ).into());
+                }
+                Ok(#ident {
+                    #(#unpack_fields),*
+                })
+            }
+        }
+    })
+}
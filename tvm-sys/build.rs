@@ -3,6 +3,7 @@ extern crate bindgen;
 use std::{env, error::Error, path::PathBuf, process, result::Result};
 
 const TVM_RUNTIME: &'static str = "tvm_runtime";
+const TVM: &'static str = "tvm";
 
 fn main() {
     match run() {
@@ -15,12 +16,19 @@ fn main() {
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
-    println!("cargo:rustc-link-lib=dylib={}", TVM_RUNTIME);
-    let lib = format!("lib{}", TVM_RUNTIME);
+    // the node API is only available in the full `libtvm`
+    let (name, header) = if env::var("CARGO_FEATURE_COMPILER").is_ok() {
+        (TVM, "include/tvm/c_api.h")
+    } else {
+        (TVM_RUNTIME, "include/tvm/runtime/c_runtime_api.h")
+    };
+    println!("cargo:rustc-link-lib=dylib={}", name);
+    let lib = format!("lib{}", name);
     println!("cargo:rustc-link-search=native={}", lib);
     let tvm_home = env::var("TVM_HOME").expect("TVM_HOME not found!");
     let bindings = bindgen::Builder::default()
-        .header(format!("{}/include/tvm/runtime/c_runtime_api.h", tvm_home))
+        .header(format!("{}/{}", tvm_home, header))
+        .clang_arg(format!("-I{}/include/", tvm_home))
         .clang_arg(format!("-I{}/3rdparty/dlpack/include/", tvm_home))
         .blacklist_type("max_align_t") // https://github.com/rust-lang-nursery/rust-bindgen/issues/550
         .layout_tests(false)